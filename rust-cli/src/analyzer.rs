@@ -1,95 +1,699 @@
 //! File Analyzer Module
 
-use std::path::Path;
-use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 use walkdir::WalkDir;
-use tracing::{info, debug};
+use tracing::{info, debug, warn};
 
+use diamond_drill::archive::{self, ArchiveFormat, ArchiveOptions};
+use diamond_drill::engine::{self, EngineError, EngineOptions, FileInfo};
+use diamond_drill::entropy::EntropyClass;
+use diamond_drill::markdown::{self, BrokenLink};
+use diamond_drill::sloc::{self, SlocReport};
+
+use crate::audit::{self, PermissionAudit, WritablePath};
+use crate::budget::{Budget, BudgetLimit};
+use crate::cache::{AnalysisCache, CacheKey};
 use crate::config::Config;
+use crate::dedupe::{self, DuplicateGroup};
+use crate::disk_usage::{self, HardLinkGroup, UsageReport, UsageTotals};
+use crate::ignore_rules::{IgnoreMatcher, IgnoreRule};
+use crate::scan_errors::{IssueReason, ScanIssue, ScanIssues};
+use crate::secrets::SecretFinding;
+use crate::symlinks::{self, LinkClass, SymlinkRecord};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisResults {
     pub source_path: String,
     pub total_files: usize,
+    /// Apparent bytes, each hard-linked inode once
     pub total_size: u64,
-    pub file_types: HashMap<String, usize>,
+    /// Allocated bytes, each hard-linked inode once
+    #[serde(default)]
+    pub disk_size: u64,
+    pub file_types: BTreeMap<String, usize>,
     pub files: Vec<FileInfo>,
     pub timestamp: String,
+    /// A file or time budget ran out before the walk finished
+    #[serde(default)]
+    pub partial: bool,
+    /// The budget that ran out when `partial`
+    #[serde(default)]
+    pub limit_hit: Option<BudgetLimit>,
+    /// Include/exclude and ignore-file rules applied during the walk
+    #[serde(default)]
+    pub ignore_rules: Vec<IgnoreRule>,
+    /// Sets of files with identical content
+    #[serde(default)]
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    /// Relative Markdown links and anchors that do not resolve
+    #[serde(default)]
+    pub broken_links: Vec<BrokenLink>,
+    /// Possible credentials found by the secret scanner
+    #[serde(default)]
+    pub secrets: Vec<SecretFinding>,
+    /// World-writable paths, setuid/setgid files, unknown owners and usage per owner
+    #[serde(default)]
+    pub audit: PermissionAudit,
+    /// Every symbolic link met during the walk, classified
+    #[serde(default)]
+    pub symlinks: Vec<SymlinkRecord>,
+    /// Files sharing an inode
+    #[serde(default)]
+    pub hard_links: Vec<HardLinkGroup>,
+    /// Per-directory totals, largest files and directories, size histogram
+    #[serde(default)]
+    pub usage: UsageReport,
+    /// Code, comment and blank lines by language, overall and per directory
+    #[serde(default)]
+    pub sloc: SlocReport,
+    /// Paths that could not be read, by path
+    #[serde(default)]
+    pub errors: Vec<ScanIssue>,
+    /// Paths left out on purpose (too large, excluded, special files), by path
+    #[serde(default)]
+    pub skipped: Vec<ScanIssue>,
 }
 
+/// Trailing record of an NDJSON stream: the report without its file list.
+///
+/// Duplicate groups, broken links and secret findings need every file at
+/// once and are not part of it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileInfo {
-    pub path: String,
-    pub size: u64,
-    pub file_type: String,
-    pub modified: Option<String>,
-    pub checksum: Option<String>,
+pub struct StreamSummary {
+    pub source_path: String,
+    pub total_files: usize,
+    pub total_size: u64,
+    #[serde(default)]
+    pub disk_size: u64,
+    pub file_types: BTreeMap<String, usize>,
+    pub timestamp: String,
+    #[serde(default)]
+    pub partial: bool,
+    #[serde(default)]
+    pub limit_hit: Option<BudgetLimit>,
+    pub ignore_rules: Vec<IgnoreRule>,
+    #[serde(default)]
+    pub audit: PermissionAudit,
+    #[serde(default)]
+    pub symlinks: Vec<SymlinkRecord>,
+    #[serde(default)]
+    pub hard_links: Vec<HardLinkGroup>,
+    #[serde(default)]
+    pub usage: UsageReport,
+    #[serde(default)]
+    pub sloc: SlocReport,
+    #[serde(default)]
+    pub errors: Vec<ScanIssue>,
+    #[serde(default)]
+    pub skipped: Vec<ScanIssue>,
+}
+
+/// One line of NDJSON output
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum StreamRecord<'a> {
+    File(&'a FileInfo),
+    Summary(&'a StreamSummary),
+}
+
+/// Message of `excluded` skips
+const EXCLUDED: &str = "matched an exclude pattern or ignore file, or no include pattern";
+
+/// Jobs queued per worker before the walker blocks
+const QUEUE_DEPTH_PER_WORKER: usize = 64;
+
+/// A walk entry tagged with its position in walk order
+struct WalkItem {
+    index: usize,
+    path: PathBuf,
+}
+
+/// What the walker saw besides the files it queued
+#[derive(Default)]
+struct WalkReport {
+    /// Include/exclude and ignore-file rules applied during the walk
+    ignore_rules: Vec<IgnoreRule>,
+    /// World-writable directories, in walk order
+    writable_dirs: Vec<WritablePath>,
+    /// Symbolic links, in walk order
+    symlinks: Vec<SymlinkRecord>,
+    /// Unreadable and skipped paths from the walker and workers, by path
+    issues: ScanIssues,
+    /// The budget that stopped the walk early
+    limit_hit: Option<BudgetLimit>,
 }
 
 /// Analyze a source path
+///
+/// One thread walks the tree (sorted by file name) and feeds a bounded queue;
+/// a pool of `[analysis] workers` threads stats and hashes the files. Results
+/// are put back into walk order so identical trees produce identical reports.
 pub async fn analyze(source: &str, config: &Config) -> Result<AnalysisResults> {
-    let source_path = Path::new(source);
+    let (results, _) = analyze_cached(source, config, None).await?;
+    Ok(results)
+}
+
+/// Analyze a source path, reusing and refreshing an incremental cache.
+///
+/// The cache is handed back so the caller decides whether to persist it.
+pub async fn analyze_cached(
+    source: &str,
+    config: &Config,
+    cache: Option<AnalysisCache>,
+) -> Result<(AnalysisResults, Option<AnalysisCache>)> {
+    let source = source.to_string();
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || {
+        let results = analyze_blocking(&source, &config, cache.as_ref())?;
+        if let Some(cache) = &cache {
+            info!("♻️  {} of {} files served from cache", cache.hits(), results.total_files);
+        }
+        Ok((results, cache))
+    })
+    .await?
+}
+
+/// Analyze a source path, writing NDJSON to `dest` (`-` for stdout).
+///
+/// Each file becomes one `{"type":"file",...}` line as soon as a worker has
+/// analyzed it, and a `{"type":"summary",...}` line closes the stream. Files
+/// are not retained, so memory stays flat however large the tree is: only
+/// multiply-linked files are kept, to group hard links. Duplicate detection
+/// and link checking need the full file list and are skipped, as is the
+/// analysis cache, whose index grows with the tree.
+pub async fn analyze_ndjson(source: &str, config: &Config, dest: &str) -> Result<StreamSummary> {
+    let source = source.to_string();
+    let config = config.clone();
+    let dest = dest.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut out: Box<dyn Write + Send> = if dest == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(LineWriter::new(File::create(&dest)?))
+        };
+
+        let mut summary = StreamSummary {
+            source_path: source.clone(),
+            total_files: 0,
+            total_size: 0,
+            disk_size: 0,
+            file_types: BTreeMap::new(),
+            timestamp: chrono_lite_now(),
+            partial: false,
+            limit_hit: None,
+            ignore_rules: Vec::new(),
+            audit: PermissionAudit::default(),
+            symlinks: Vec::new(),
+            hard_links: Vec::new(),
+            usage: UsageReport::default(),
+            sloc: SlocReport::default(),
+            errors: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        if config.analysis.find_duplicates {
+            info!("🧬 Duplicate detection skipped: not available when streaming NDJSON");
+        }
+        info!("🔗 Markdown link checking skipped: not available when streaming NDJSON");
+
+        let mut usage = UsageTotals::new(&source, config.analysis.top_n);
+        let mut audit = audit::AuditTotals::default();
+        let mut linked = Vec::new();
+        // Workers finish out of walk order; records wait here until every
+        // earlier file is written so the stream matches a single-worker run
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        let walked = run_pipeline(&source, &config, None, |index, infos| {
+            pending.insert(index, infos);
+            while let Some(infos) = pending.remove(&next) {
+                next += 1;
+                for info in infos {
+                    *summary.file_types.entry(info.file_type.clone()).or_insert(0) += 1;
+                    summary.total_files += 1;
+                    usage.add(&info);
+                    audit.add(&info);
+                    summary.sloc.add(Path::new(&source), &info);
+                    write_record(&mut out, &StreamRecord::File(&info))?;
+                    if info.storage.as_ref().is_some_and(|s| s.links > 1) {
+                        linked.push(info);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        summary.total_size = usage.apparent;
+        summary.disk_size = usage.on_disk;
+        summary.usage = usage.report();
+        summary.partial = walked.limit_hit.is_some();
+        summary.limit_hit = walked.limit_hit;
+        summary.ignore_rules = walked.ignore_rules;
+        summary.audit = audit.report(walked.writable_dirs);
+        summary.symlinks = walked.symlinks;
+        summary.hard_links = disk_usage::hard_link_groups(&linked);
+        summary.errors = walked.issues.errors;
+        summary.skipped = walked.issues.skipped;
+        write_record(&mut out, &StreamRecord::Summary(&summary))?;
+        out.flush()?;
+
+        info!("📊 Streamed {} files, {} bytes ({} on disk)",
+              summary.total_files, summary.total_size, summary.disk_size);
+        Ok(summary)
+    })
+    .await?
+}
+
+fn analyze_blocking(source: &str, config: &Config, cache: Option<&AnalysisCache>) -> Result<AnalysisResults> {
+    let mut collected = Vec::new();
+    let walked = run_pipeline(source, config, cache, |index, infos| {
+        collected.push((index, infos));
+        Ok(())
+    })?;
+    collected.sort_by_key(|(index, _)| *index);
+
     let mut results = AnalysisResults {
         source_path: source.to_string(),
         total_files: 0,
         total_size: 0,
-        file_types: HashMap::new(),
-        files: Vec::new(),
+        disk_size: 0,
+        file_types: BTreeMap::new(),
+        files: Vec::with_capacity(collected.len()),
         timestamp: chrono_lite_now(),
+        partial: walked.limit_hit.is_some(),
+        limit_hit: walked.limit_hit,
+        ignore_rules: walked.ignore_rules,
+        duplicate_groups: Vec::new(),
+        broken_links: Vec::new(),
+        secrets: Vec::new(),
+        audit: PermissionAudit::default(),
+        symlinks: walked.symlinks,
+        hard_links: Vec::new(),
+        usage: UsageReport::default(),
+        sloc: SlocReport::default(),
+        errors: walked.issues.errors,
+        skipped: walked.issues.skipped,
     };
 
+    // Archive entries are already counted in their archive, hard links once
+    let mut usage = UsageTotals::new(source, config.analysis.top_n);
+    for info in collected.into_iter().flat_map(|(_, infos)| infos) {
+        *results.file_types.entry(info.file_type.clone()).or_insert(0) += 1;
+        results.total_files += 1;
+        usage.add(&info);
+        results.files.push(info);
+    }
+    results.total_size = usage.apparent;
+    results.disk_size = usage.on_disk;
+    results.usage = usage.report();
+    if let Some(top) = results.usage.top_directories.first() {
+        info!("🗂️  {} directories, largest {} ({} bytes)",
+              results.usage.directories.len(), top.path, top.size);
+    }
+
+    results.sloc = sloc::summarize(Path::new(source), &results.files);
+    if !results.sloc.languages.is_empty() {
+        let total = results.sloc.total();
+        info!("🧮 {} source files in {} languages: {} code, {} comment, {} blank lines",
+              total.files, results.sloc.languages.len(),
+              total.counts.code, total.counts.comments, total.counts.blanks);
+    }
+
+    results.hard_links = disk_usage::hard_link_groups(&results.files);
+    if !results.hard_links.is_empty() {
+        info!("🪢 {} hard-linked inodes", results.hard_links.len());
+    }
+
+    if config.analysis.find_duplicates {
+        results.duplicate_groups = dedupe::find_duplicates(&results.files);
+        let wasted: u64 = results.duplicate_groups.iter().map(|g| g.wasted_bytes).sum();
+        info!("🧬 {} duplicate groups, {} bytes reclaimable",
+              results.duplicate_groups.len(), wasted);
+    }
+
+    results.broken_links = markdown::check_links(&results.files);
+    if !results.broken_links.is_empty() {
+        info!("🔗 {} broken Markdown links", results.broken_links.len());
+    }
+
+    let encrypted = results.files.iter()
+        .filter(|f| f.entropy.as_ref().is_some_and(|e| e.class == EntropyClass::Encrypted))
+        .count();
+    if encrypted > 0 {
+        info!("🔐 {} files look encrypted", encrypted);
+    }
+
+    results.audit = audit::audit(&results.files, walked.writable_dirs);
+    if results.audit.findings() > 0 {
+        info!("🛡️  {} world-writable paths, {} setuid/setgid files, {} files with unknown owners",
+              results.audit.world_writable.len(), results.audit.setuid.len(),
+              results.audit.unknown_owners.len());
+    }
+
+    let unsafe_links: Vec<LinkClass> = results.symlinks.iter()
+        .map(|l| l.class)
+        .filter(|&c| c != LinkClass::Internal)
+        .collect();
+    if !unsafe_links.is_empty() {
+        let count = |f: fn(&LinkClass) -> bool| unsafe_links.iter().filter(|c| f(c)).count();
+        info!("⛓️  {} symlinks: {} dangling, {} looping, {} escaping the source",
+              results.symlinks.len(),
+              count(|c| *c == LinkClass::Dangling),
+              count(|c| *c == LinkClass::Loop),
+              count(|c| c.escapes()));
+    }
+
+    let documents: Vec<_> = results.files.iter().filter_map(|f| f.metadata.as_ref()).collect();
+    if !documents.is_empty() {
+        let located = documents.iter().filter(|m| m.gps.is_some()).count();
+        info!("🏷️  {} files carry embedded metadata, {} with GPS coordinates",
+              documents.len(), located);
+    }
+
+    if !results.errors.is_empty() {
+        warn!("⚠️  {} paths could not be analyzed", results.errors.len());
+    }
+    if !results.skipped.is_empty() {
+        info!("⏭️  {} paths skipped", results.skipped.len());
+    }
+    if let Some(limit) = results.limit_hit {
+        warn!("⏱️  Partial report: {} ran out", limit);
+    }
+
+    info!("📊 Analysis complete: {} files, {} bytes ({} on disk)",
+          results.total_files, results.total_size, results.disk_size);
+
+    Ok(results)
+}
+
+/// Walk and analyze the tree, handing each queued file's records to `sink`
+/// in completion order together with its walk index: the file followed by
+/// any archive entries, or nothing when it was skipped, so every index is
+/// delivered exactly once. An error from `sink` stops the walk.
+fn run_pipeline<F>(
+    source: &str,
+    config: &Config,
+    cache: Option<&AnalysisCache>,
+    mut sink: F,
+) -> Result<WalkReport>
+where
+    F: FnMut(usize, Vec<FileInfo>) -> Result<()>,
+{
+    let workers = worker_count(config);
+    let options = config.analysis.engine_options();
+    let archives = config.archives.archive_options();
+    debug!("Analyzing with {} workers", workers);
+
+    let (job_tx, job_rx) = mpsc::sync_channel::<WalkItem>(workers * QUEUE_DEPTH_PER_WORKER);
+    let job_rx = Mutex::new(job_rx);
+    let (result_tx, result_rx) = mpsc::sync_channel::<(usize, Vec<FileInfo>)>(workers * QUEUE_DEPTH_PER_WORKER);
+    let cancelled = AtomicBool::new(false);
+    let issues = Mutex::new(ScanIssues::default());
+    let budget = Budget::start(&config.analysis);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let job_rx = &job_rx;
+            let options = &options;
+            let archives = archives.as_ref();
+            let issues = &issues;
+            let budget = &budget;
+            let result_tx = result_tx.clone();
+            scope.spawn(move || loop {
+                let item = match job_rx.lock() {
+                    Ok(rx) => match rx.recv() {
+                        Ok(item) => item,
+                        Err(_) => break,
+                    },
+                    Err(_) => break,
+                };
+                let infos = if drop_over_budget(&item.path, budget, issues) {
+                    Vec::new()
+                } else {
+                    analyze_entry(&item.path, options, archives, cache, issues)
+                };
+                if result_tx.send((item.index, infos)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let cancelled = &cancelled;
+        let walk_issues = &issues;
+        let walk_budget = &budget;
+        let walker = scope.spawn(move || walk(source, config, job_tx, cancelled, walk_issues, walk_budget));
+
+        // Keep draining after a sink error so workers never block on a full queue
+        let mut outcome = Ok(());
+        for (index, infos) in result_rx.iter() {
+            if outcome.is_ok() {
+                outcome = sink(index, infos);
+                if outcome.is_err() {
+                    cancelled.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let mut walked = walker.join()
+            .map_err(|_| anyhow::anyhow!("Directory walker panicked"))?;
+        walked.issues = std::mem::take(&mut *issues.lock().unwrap_or_else(|e| e.into_inner())).sorted();
+        walked.limit_hit = budget.exhausted();
+        outcome.map(|_| walked)
+    })
+}
+
+/// Queued files are dropped, not analyzed, once time is up; each is listed
+/// as skipped so the partial report still accounts for it
+fn drop_over_budget(path: &Path, budget: &Budget, issues: &Mutex<ScanIssues>) -> bool {
+    if !budget.expired() {
+        return false;
+    }
+    let issue = ScanIssue::new(path, IssueReason::TimeBudget, "time_budget ran out before analysis");
+    ScanIssues::record(issues, issue);
+    true
+}
+
+fn write_record(out: &mut dyn Write, record: &StreamRecord) -> Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Walk the source tree, queueing every non-directory entry in order.
+/// Symlinks are classified before they are followed; links that cannot or
+/// may not be followed are recorded and pruned, as are excluded entries,
+/// entries beyond the depth or filesystem limits and unreadable directories.
+/// The walk ends early when `budget` runs out.
+fn walk(
+    source: &str,
+    config: &Config,
+    jobs: SyncSender<WalkItem>,
+    cancelled: &AtomicBool,
+    issues: &Mutex<ScanIssues>,
+    budget: &Budget,
+) -> WalkReport {
+    let source_path = Path::new(source);
+    let root = source_path.canonicalize().unwrap_or_else(|_| source_path.to_path_buf());
+    let follow = config.analysis.follow_symlinks;
+    let links = RefCell::new(Vec::new());
+    let mut matcher = IgnoreMatcher::new(source_path, &config.analysis);
+    let root_device = config.analysis.one_file_system
+        .then(|| source_path.metadata().ok().and_then(|m| device_of(&m)))
+        .flatten();
     let walker = WalkDir::new(source_path)
-        .follow_links(config.analysis.follow_symlinks)
+        .follow_links(follow)
+        .same_file_system(cfg!(not(unix)) && config.analysis.one_file_system)
+        .sort_by_file_name()
         .into_iter()
-        .filter_map(|e| e.ok());
+        .filter_entry(|e| {
+            if matcher.is_excluded(e.path(), e.file_type().is_dir(), e.depth()) {
+                ScanIssues::record(issues, ScanIssue::new(e.path(), IssueReason::Excluded, EXCLUDED));
+                return false;
+            }
+            if let Some(max) = config.analysis.max_depth.filter(|max| e.depth() > *max) {
+                let message = format!("deeper than max_depth {}", max);
+                ScanIssues::record(issues, ScanIssue::new(e.path(), IssueReason::TooDeep, message));
+                return false;
+            }
+            // Metadata follows links when following, so links into other mounts count too
+            if e.depth() > 0 && root_device.is_some()
+                && e.metadata().ok().and_then(|m| device_of(&m)).is_some_and(|d| Some(d) != root_device)
+            {
+                let issue = ScanIssue::new(e.path(), IssueReason::OtherFilesystem, "on another filesystem");
+                ScanIssues::record(issues, issue);
+                return false;
+            }
+            if e.depth() == 0 || !e.path_is_symlink() {
+                return true;
+            }
+            let mut link = symlinks::inspect(e.path(), &root);
+            link.followed = follow && link.class.followable(config.analysis.ro_locked);
+            // Unfollowed links are kept (and skipped below) when not following
+            let keep = link.followed || !follow;
+            links.borrow_mut().push(link);
+            keep
+        });
 
+    let mut writable_dirs = Vec::new();
+    let mut index = 0;
     for entry in walker {
-        let path = entry.path();
+        let entry = match entry {
+            Ok(entry) => entry,
+            // Dangling and looping links surface as errors when following
+            Err(e) => {
+                let path = e.path().unwrap_or(source_path);
+                if e.depth() > 0 && path.is_symlink() {
+                    links.borrow_mut().push(symlinks::inspect(path, &root));
+                } else {
+                    let issue = match e.io_error() {
+                        Some(io) => ScanIssue::from_io(path, io),
+                        None => ScanIssue::new(path, IssueReason::Io, e.to_string()),
+                    };
+                    ScanIssues::record(issues, issue);
+                }
+                continue;
+            }
+        };
+        if entry.file_type().is_symlink() {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            if let Some(dir) = entry.metadata().ok().and_then(|m| audit::writable_dir(entry.path(), &m)) {
+                writable_dirs.push(dir);
+            }
+            continue;
+        }
+        if !budget.admit(index) {
+            break;
+        }
+        let item = WalkItem { index, path: entry.into_path() };
+        index += 1;
+        if cancelled.load(Ordering::Relaxed) || jobs.send(item).is_err() {
+            break;
+        }
+    }
 
-        if path.is_file() {
-            if let Ok(metadata) = path.metadata() {
-                let size = metadata.len();
+    WalkReport {
+        ignore_rules: matcher.into_rules(),
+        writable_dirs,
+        symlinks: links.into_inner(),
+        issues: ScanIssues::default(),
+        limit_hit: None,
+    }
+}
 
-                // Skip files larger than max
-                if size > config.analysis.max_file_size as u64 {
-                    debug!("Skipping large file: {}", path.display());
-                    continue;
-                }
+/// Device a path lives on; `None` where `[analysis] one_file_system` is left to walkdir
+#[cfg(unix)]
+fn device_of(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
 
-                let ext = path.extension()
-                    .map(|e| e.to_string_lossy().to_lowercase())
-                    .unwrap_or_else(|| "unknown".to_string());
-
-                *results.file_types.entry(ext.clone()).or_insert(0) += 1;
-                results.total_files += 1;
-                results.total_size += size;
-
-                let modified = metadata.modified().ok().map(|t| {
-                    format!("{:?}", t)
-                });
-
-                results.files.push(FileInfo {
-                    path: path.display().to_string(),
-                    size,
-                    file_type: ext,
-                    modified,
-                    checksum: None, // Could add SHA256 here
-                });
-            }
+#[cfg(not(unix))]
+fn device_of(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Stat and analyze a single file, followed by its entries when it is an
+/// archive and archive listing is on; empty for non-files and skipped files
+fn analyze_entry(
+    path: &Path,
+    options: &EngineOptions,
+    archives: Option<&ArchiveOptions>,
+    cache: Option<&AnalysisCache>,
+    issues: &Mutex<ScanIssues>,
+) -> Vec<FileInfo> {
+    let Some(mut info) = analyze_file(path, options, cache, issues) else {
+        return Vec::new();
+    };
+
+    // Entries are never cached, so archives are listed again on every run
+    let entries = match archives {
+        Some(limits) if ArchiveFormat::of(&info).is_some() => {
+            archive::expand_path(path, &mut info, options, limits).unwrap_or_else(|e| {
+                debug!("Cannot open archive {}: {}", path.display(), e);
+                Vec::new()
+            })
         }
+        _ => Vec::new(),
+    };
+
+    let mut infos = Vec::with_capacity(entries.len() + 1);
+    infos.push(info);
+    infos.extend(entries);
+    infos
+}
+
+/// Stat and analyze a single file; `None`, with the reason recorded in
+/// `issues`, for non-files and files that are skipped or unreadable
+fn analyze_file(
+    path: &Path,
+    options: &EngineOptions,
+    cache: Option<&AnalysisCache>,
+    issues: &Mutex<ScanIssues>,
+) -> Option<FileInfo> {
+    let metadata = match path.metadata() {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            ScanIssues::record(issues, ScanIssue::from_io(path, &e));
+            return None;
+        }
+    };
+    if !metadata.is_file() {
+        ScanIssues::record(issues, ScanIssue::special(path, metadata.file_type()));
+        return None;
     }
 
-    info!("📊 Analysis complete: {} files, {} bytes",
-          results.total_files, results.total_size);
+    // Skip files larger than max
+    if metadata.len() > options.max_file_size {
+        let too_large = EngineError::TooLarge { size: metadata.len(), limit: options.max_file_size };
+        ScanIssues::record(issues, ScanIssue::from_engine(path, &too_large));
+        return None;
+    }
 
-    Ok(results)
+    let cache_key = CacheKey::from_metadata(&metadata);
+    if let Some(info) = cache.and_then(|c| c.lookup(&cache_key, path, &metadata, options)) {
+        return Some(info);
+    }
+
+    match engine::analyze_with_metadata(path, &metadata, options) {
+        Ok(info) => {
+            if let Some(cache) = cache {
+                cache.store(cache_key, &info);
+            }
+            Some(info)
+        }
+        Err(e) => {
+            ScanIssues::record(issues, ScanIssue::from_engine(path, &e));
+            None
+        }
+    }
+}
+
+/// Configured worker count, or one per available CPU when set to 0
+fn worker_count(config: &Config) -> usize {
+    match config.analysis.workers {
+        0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n,
+    }
 }
 
-/// Export analysis results
+/// Export analysis results (`-` writes to stdout)
 pub async fn export(results: &AnalysisResults, dest: &str) -> Result<()> {
     let content = serde_json::to_string_pretty(results)?;
+    if dest == "-" {
+        println!("{}", content);
+        return Ok(());
+    }
     tokio::fs::write(dest, content).await?;
     info!("📤 Exported to: {}", dest);
     Ok(())
@@ -107,6 +711,7 @@ fn chrono_lite_now() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use diamond_drill::hasher::HashAlgorithm;
     use tempfile::tempdir;
     use std::fs;
 
@@ -122,6 +727,212 @@ mod tests {
         assert_eq!(results.total_files, 2);
         assert!(results.file_types.contains_key("txt"));
         assert!(results.file_types.contains_key("md"));
+        assert_eq!(results.files[0].line_count, Some(1));
+        assert_eq!(results.files[0].word_count, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_honors_ignore_rules() {
+        let temp = tempdir().unwrap();
+        fs::create_dir_all(temp.path().join("target/debug")).unwrap();
+        fs::write(temp.path().join("target/debug/app"), "bin").unwrap();
+        fs::write(temp.path().join(".diamondignore"), "target/\n").unwrap();
+        fs::write(temp.path().join("main.rs"), "fn main() {}").unwrap();
+
+        let config = Config::default();
+        let results = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+
+        assert_eq!(results.total_files, 2); // main.rs + .diamondignore
+        assert!(results.files.iter().all(|f| !f.path.contains("target")));
+        assert!(results.ignore_rules.iter().any(|r| r.pattern == "target/"));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_sniffs_content() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("notes.pdf"), "not a pdf at all").unwrap();
+        fs::write(temp.path().join("run"), "#!/bin/sh\necho hi\n").unwrap();
+
+        let config = Config::default();
+        let results = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+
+        let pdf = results.files.iter().find(|f| f.path.ends_with("notes.pdf")).unwrap();
+        assert_eq!(pdf.content_type.mime_type, "text/plain");
+        assert!(pdf.content_type.extension_mismatch);
+
+        let script = results.files.iter().find(|f| f.path.ends_with("run")).unwrap();
+        assert_eq!(script.file_type, "sh");
+        assert_eq!(script.content_type.mime_type, "text/x-shellscript");
+    }
+
+    #[tokio::test]
+    async fn test_analyze_lists_archives() {
+        use std::io::Write;
+
+        let temp = tempdir().unwrap();
+        let mut zip = zip::ZipWriter::new(fs::File::create(temp.path().join("a.zip")).unwrap());
+        zip.start_file("inside.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"packed").unwrap();
+        zip.finish().unwrap();
+        fs::write(temp.path().join("b.txt"), "plain").unwrap();
+        let zip_size = fs::metadata(temp.path().join("a.zip")).unwrap().len();
+
+        let mut config = Config::default();
+        let results = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+        assert_eq!(results.total_files, 2);
+
+        config.archives.enabled = true;
+        let results = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+        let root = format!("{}/", temp.path().display());
+        let paths: Vec<&str> = results.files.iter()
+            .map(|f| f.path.strip_prefix(&root).unwrap())
+            .collect();
+        assert_eq!(paths, vec!["a.zip", "a.zip!/inside.txt", "b.txt"]);
+        assert_eq!(results.total_files, 3);
+        assert_eq!(results.total_size, zip_size + 5);
+        assert_eq!(results.files[0].archive.as_ref().unwrap().entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_reports_broken_links() {
+        let temp = tempdir().unwrap();
+        fs::create_dir(temp.path().join("docs")).unwrap();
+        fs::write(temp.path().join("index.md"), "# Home\n[guide](docs/guide.md) [old](docs/old.md)\n").unwrap();
+        fs::write(temp.path().join("docs/guide.md"), "# Guide\n[home](../index.md#home)\n").unwrap();
+
+        let config = Config::default();
+        let results = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+
+        let guide = results.files.iter().find(|f| f.path.ends_with("guide.md")).unwrap();
+        assert_eq!(guide.markdown.as_ref().unwrap().headings[0].text, "Guide");
+        assert_eq!(results.broken_links.len(), 1);
+        assert_eq!(results.broken_links[0].target, "docs/old.md");
+    }
+
+    #[tokio::test]
+    async fn test_analyze_computes_checksums() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("abc.txt"), "abc").unwrap();
+
+        let mut config = Config::default();
+        config.analysis.hash_algorithms = vec![HashAlgorithm::Sha256, HashAlgorithm::Sha512];
+        let results = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+
+        let file = &results.files[0];
+        assert_eq!(
+            file.checksum.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(file.hashes.len(), 2);
+        assert!(file.hashes.contains_key(&HashAlgorithm::Sha512));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_order_is_deterministic() {
+        let temp = tempdir().unwrap();
+        for dir in ["b", "a", "c/d"] {
+            fs::create_dir_all(temp.path().join(dir)).unwrap();
+            for name in ["z.txt", "m.txt", "a.txt"] {
+                fs::write(temp.path().join(dir).join(name), name).unwrap();
+            }
+        }
+
+        let mut config = Config::default();
+        config.analysis.workers = 1;
+        let serial = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+        config.analysis.workers = 8;
+        let parallel = analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+
+        let paths = |r: &AnalysisResults| r.files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(serial.total_files, 9);
+        assert_eq!(paths(&serial), paths(&parallel));
+        assert!(paths(&serial)[0].ends_with("a/a.txt"));
+        assert!(paths(&serial)[8].ends_with("c/d/z.txt"));
+    }
+
+    #[test]
+    fn test_dropped_files_are_skipped() {
+        let issues = Mutex::new(ScanIssues::default());
+        let mut config = Config::default();
+        let path = Path::new("/src/queued.txt");
+
+        assert!(!drop_over_budget(path, &Budget::start(&config.analysis), &issues));
+        config.analysis.time_budget = Some(0);
+        assert!(drop_over_budget(path, &Budget::start(&config.analysis), &issues));
+
+        let issues = issues.into_inner().unwrap();
+        assert!(issues.errors.is_empty());
+        assert_eq!(issues.skipped.len(), 1);
+        assert_eq!(issues.skipped[0].reason, IssueReason::TimeBudget);
+        assert_eq!(issues.skipped[0].path, "/src/queued.txt");
+    }
+
+    #[tokio::test]
+    async fn test_analyze_ndjson_stream() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("src");
+        fs::create_dir(&source).unwrap();
+        fs::write(source.join("a.txt"), "alpha").unwrap();
+        fs::write(source.join("b.md"), "beta").unwrap();
+        fs::hard_link(source.join("a.txt"), source.join("c.txt")).unwrap();
+        let dest = temp.path().join("out.ndjson");
+
+        let config = Config::default();
+        let summary = analyze_ndjson(
+            source.to_str().unwrap(), &config, dest.to_str().unwrap(),
+        ).await.unwrap();
+        assert_eq!(summary.total_files, 3);
+        assert_eq!(summary.total_size, 9);
+        assert_eq!(summary.hard_links.len(), 1);
+        assert_eq!(summary.hard_links[0].paths.len(), 2);
+        assert_eq!(summary.audit.owners[0].files, 2);
+        assert_eq!(summary.audit.owners[0].bytes, 9);
+
+        let lines: Vec<serde_json::Value> = fs::read_to_string(&dest).unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[..3].iter().all(|l| l["type"] == "file" && l["checksum"].is_string()));
+        assert_eq!(lines[3]["type"], "summary");
+        assert_eq!(lines[3]["file_types"]["md"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_ndjson_order_is_deterministic() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("src");
+        for dir in ["b", "a", "c/d"] {
+            fs::create_dir_all(source.join(dir)).unwrap();
+            for (i, name) in ["z.txt", "m.txt", "a.txt", "k.txt"].iter().enumerate() {
+                // Uneven sizes so workers finish out of walk order
+                let path = source.join(dir).join(name);
+                fs::write(&path, name.repeat(1 << (4 * (3 - i)))).unwrap();
+                // atime ahead of mtime is left alone under relatime, so
+                // reading during the first run does not change the second
+                let now = std::time::SystemTime::now();
+                let times = fs::FileTimes::new()
+                    .set_accessed(now)
+                    .set_modified(now - std::time::Duration::from_secs(60));
+                File::options().write(true).open(&path).unwrap().set_times(times).unwrap();
+            }
+        }
+
+        let mut config = Config::default();
+        config.analysis.workers = 4;
+        let mut runs = Vec::new();
+        for run in ["first.ndjson", "second.ndjson"] {
+            let dest = temp.path().join(run);
+            analyze_ndjson(source.to_str().unwrap(), &config, dest.to_str().unwrap()).await.unwrap();
+            let out = fs::read_to_string(&dest).unwrap();
+            // The summary line carries the run's timestamp
+            let files = out.lines().filter(|l| l.starts_with(r#"{"type":"file""#)).collect::<Vec<_>>().join("\n");
+            runs.push(files);
+        }
+
+        assert_eq!(runs[0].lines().count(), 12);
+        assert_eq!(runs[0], runs[1]);
+        assert!(runs[0].lines().next().unwrap().contains("a/a.txt"));
     }
 
     #[tokio::test]
@@ -133,9 +944,23 @@ mod tests {
             source_path: "/test".to_string(),
             total_files: 1,
             total_size: 100,
-            file_types: HashMap::new(),
+            disk_size: 100,
+            file_types: BTreeMap::new(),
             files: vec![],
             timestamp: "12345".to_string(),
+            partial: false,
+            limit_hit: None,
+            ignore_rules: vec![],
+            duplicate_groups: vec![],
+            broken_links: vec![],
+            secrets: vec![],
+            audit: PermissionAudit::default(),
+            symlinks: vec![],
+            hard_links: vec![],
+            usage: UsageReport::default(),
+            sloc: SlocReport::default(),
+            errors: vec![],
+            skipped: vec![],
         };
 
         export(&results, dest.to_str().unwrap()).await.unwrap();
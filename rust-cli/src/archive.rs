@@ -0,0 +1,461 @@
+//! Archive Introspection Module
+//!
+//! Lists the entries of zip, jar, tar and tar.gz files and analyzes each one
+//! as a virtual child named `archive.zip!/path/in/archive`. Everything happens
+//! in memory; nothing is ever extracted to disk. Nested archives are opened up
+//! to a depth limit, and a shared budget of expanded bytes, entries and
+//! compression ratio stops zip bombs before they expand.
+
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{self, EngineOptions, FileInfo};
+
+/// Container formats that can be listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar")]
+    Tar,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Format of an analyzed file, from its extension or sniffed type
+    pub fn of(info: &FileInfo) -> Option<Self> {
+        let kinds = [info.content_type.kind.as_deref().unwrap_or_default(), info.file_type.as_str()];
+        let is = |names: &[&str]| kinds.iter().any(|k| names.contains(k));
+
+        if is(&["zip", "jar", "war", "ear"]) {
+            Some(ArchiveFormat::Zip)
+        } else if is(&["tar"]) {
+            Some(ArchiveFormat::Tar)
+        } else if is(&["tgz"]) || (is(&["gz"]) && info.path.to_lowercase().ends_with(".tar.gz")) {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Limits applied while expanding an archive and everything nested in it
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    /// Archive nesting levels opened; 1 lists only the top-level archive
+    pub max_depth: u32,
+    /// Total bytes decompressed across all entries
+    pub max_total_size: u64,
+    /// Largest uncompressed/compressed ratio accepted for an entry or archive
+    pub max_ratio: f64,
+    /// Entries listed across all nesting levels
+    pub max_entries: usize,
+}
+
+impl Default for ArchiveOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_total_size: 1024 * 1024 * 1024,
+            max_ratio: 100.0,
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Summary attached to an archive that was listed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    pub format: ArchiveFormat,
+    /// Entries listed (directories excluded)
+    pub entries: u64,
+    /// Sum of the listed entries' uncompressed sizes
+    pub expanded_size: u64,
+    /// `expanded_size` over the archive's own size
+    pub ratio: f64,
+    /// Why the listing stopped early (limit hit or unreadable archive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incomplete: Option<String>,
+}
+
+/// Where a virtual file came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path of the containing archive (itself virtual when nested)
+    pub archive: String,
+    /// Nesting level: 1 for entries of an archive on disk
+    pub depth: u32,
+    /// Stored size; zip only, tar entries are not compressed individually
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressed_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<f64>,
+    /// Why the content was not analyzed (limit, encryption, read error)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// Expand an archive on disk. Sets `info.archive` and returns the entries,
+/// nested archive contents following the archive that holds them
+pub fn expand_path(
+    path: &Path,
+    info: &mut FileInfo,
+    engine: &EngineOptions,
+    options: &ArchiveOptions,
+) -> io::Result<Vec<FileInfo>> {
+    let Some(format) = ArchiveFormat::of(info) else {
+        return Ok(Vec::new());
+    };
+    let file = File::open(path)?;
+    let mut budget = Budget::new(options);
+    let mut out = Vec::new();
+    info.archive = Some(expand(format, file, info, 1, engine, &mut budget, &mut out));
+    Ok(out)
+}
+
+/// Expansion limits shared by every nesting level of one archive
+struct Budget<'a> {
+    options: &'a ArchiveOptions,
+    expanded: u64,
+    entries: usize,
+}
+
+impl<'a> Budget<'a> {
+    fn new(options: &'a ArchiveOptions) -> Self {
+        Self { options, expanded: 0, entries: 0 }
+    }
+
+    fn remaining(&self) -> u64 {
+        self.options.max_total_size.saturating_sub(self.expanded)
+    }
+}
+
+/// Raised when a limit ends the listing of the current archive
+struct Stop(String);
+
+/// One listed entry before analysis
+struct Listed {
+    name: String,
+    size: u64,
+    compressed_size: Option<u64>,
+    mode: Option<u32>,
+}
+
+fn expand<R: Read + Seek>(
+    format: ArchiveFormat,
+    reader: R,
+    container: &FileInfo,
+    depth: u32,
+    engine: &EngineOptions,
+    budget: &mut Budget,
+    out: &mut Vec<FileInfo>,
+) -> ArchiveInfo {
+    let mut summary = ArchiveInfo {
+        format,
+        entries: 0,
+        expanded_size: 0,
+        ratio: 0.0,
+        incomplete: None,
+    };
+
+    let result = match format {
+        ArchiveFormat::Zip => expand_zip(reader, container, depth, engine, budget, out, &mut summary),
+        ArchiveFormat::Tar => expand_tar(reader, container, depth, engine, budget, out, &mut summary),
+        ArchiveFormat::TarGz => {
+            expand_tar(GzDecoder::new(reader), container, depth, engine, budget, out, &mut summary)
+        }
+    };
+    if let Err(Stop(reason)) = result {
+        summary.incomplete = Some(reason);
+    }
+    summary.ratio = ratio(summary.expanded_size, container.size);
+    summary
+}
+
+fn expand_zip<R: Read + Seek>(
+    reader: R,
+    container: &FileInfo,
+    depth: u32,
+    engine: &EngineOptions,
+    budget: &mut Budget,
+    out: &mut Vec<FileInfo>,
+    summary: &mut ArchiveInfo,
+) -> Result<(), Stop> {
+    let mut zip = zip::ZipArchive::new(reader).map_err(|e| Stop(format!("unreadable: {}", e)))?;
+
+    for i in 0..zip.len() {
+        let listed = match zip.by_index_raw(i) {
+            Ok(entry) if entry.is_dir() => continue,
+            Ok(entry) => Listed {
+                name: entry.name().to_string(),
+                size: entry.size(),
+                compressed_size: Some(entry.compressed_size()),
+                mode: entry.unix_mode(),
+            },
+            Err(e) => return Err(Stop(format!("unreadable: {}", e))),
+        };
+
+        let declared_ratio = listed.compressed_size.map(|c| ratio(listed.size, c));
+        let skipped = if declared_ratio.is_some_and(|r| r > budget.options.max_ratio) {
+            Some("ratio limit".to_string())
+        } else {
+            None
+        };
+        let entry = match skipped {
+            None => zip.by_index(i).map_err(|e| e.to_string()),
+            Some(_) => Err(String::new()),
+        };
+        add_entry(listed, entry, skipped, container, depth, engine, budget, out, summary)?;
+    }
+    Ok(())
+}
+
+fn expand_tar<R: Read>(
+    reader: R,
+    container: &FileInfo,
+    depth: u32,
+    engine: &EngineOptions,
+    budget: &mut Budget,
+    out: &mut Vec<FileInfo>,
+    summary: &mut ArchiveInfo,
+) -> Result<(), Stop> {
+    let mut tar = tar::Archive::new(reader);
+    let entries = tar.entries().map_err(|e| Stop(format!("unreadable: {}", e)))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| Stop(format!("unreadable: {}", e)))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // Every tar entry is decompressed to reach the next one, even when
+        // its content is skipped, so bound the stream before moving on
+        let expanded = summary.expanded_size + entry.size();
+        if expanded as f64 > budget.options.max_ratio * container.size.max(1) as f64 {
+            return Err(Stop("ratio limit".to_string()));
+        }
+        let listed = Listed {
+            name: entry.path()
+                .map(|p| p.to_string_lossy().into_owned())
+                .map_err(|e| Stop(format!("unreadable: {}", e)))?,
+            size: entry.size(),
+            compressed_size: None,
+            mode: entry.header().mode().ok(),
+        };
+        add_entry(listed, Ok(entry), None, container, depth, engine, budget, out, summary)?;
+    }
+    Ok(())
+}
+
+/// Analyze one entry and, for nested archives, what it contains
+#[allow(clippy::too_many_arguments)]
+fn add_entry<R: Read>(
+    listed: Listed,
+    content: Result<R, String>,
+    mut skipped: Option<String>,
+    container: &FileInfo,
+    depth: u32,
+    engine: &EngineOptions,
+    budget: &mut Budget,
+    out: &mut Vec<FileInfo>,
+    summary: &mut ArchiveInfo,
+) -> Result<(), Stop> {
+    if budget.entries >= budget.options.max_entries {
+        return Err(Stop("entry limit".to_string()));
+    }
+    budget.entries += 1;
+    summary.entries += 1;
+
+    let path = format!("{}!/{}", container.path, listed.name);
+    // Tar entries (no compressed size) are streamed through even when skipped
+    let streamed = listed.compressed_size.is_none();
+    if skipped.is_none() && listed.size > engine.max_file_size {
+        skipped = Some("too large".to_string());
+    }
+    if (skipped.is_none() || streamed) && listed.size > budget.remaining() {
+        return Err(Stop("total size limit".to_string()));
+    }
+
+    let data = match (skipped.is_none(), content) {
+        (true, Ok(reader)) => match read_bounded(reader, budget.remaining().min(engine.max_file_size)) {
+            Ok(Some(data)) => Some(data),
+            // More bytes than declared: a lying header
+            Ok(None) => return Err(Stop("total size limit".to_string())),
+            Err(e) => {
+                skipped = Some(e.to_string());
+                None
+            }
+        },
+        (true, Err(e)) => {
+            skipped = Some(e);
+            None
+        }
+        (false, _) => None,
+    };
+
+    let mut child = match &data {
+        Some(data) => engine::analyze_bytes(data, &path, engine),
+        None => FileInfo::new(path, listed.size),
+    };
+    if data.is_none() {
+        child.file_type = engine::extension_of(&child.path).unwrap_or_else(|| "unknown".to_string());
+    }
+    child.permissions = listed.mode.map(|m| format!("{:o}", m & 0o777));
+    child.archive_entry = Some(ArchiveEntry {
+        archive: container.path.clone(),
+        depth,
+        compressed_size: listed.compressed_size,
+        ratio: listed.compressed_size.map(|c| ratio(child.size, c)),
+        skipped,
+    });
+
+    budget.expanded += match &data {
+        Some(data) => data.len() as u64,
+        None if streamed => listed.size,
+        None => 0,
+    };
+    summary.expanded_size += child.size;
+
+    let nested = data.and_then(|data| {
+        let format = ArchiveFormat::of(&child).filter(|_| depth < budget.options.max_depth)?;
+        Some((format, data))
+    });
+    let index = out.len();
+    out.push(child);
+
+    if let Some((format, data)) = nested {
+        let container = out[index].clone();
+        let info = expand(format, Cursor::new(data), &container, depth + 1, engine, budget, out);
+        out[index].archive = Some(info);
+    }
+    Ok(())
+}
+
+/// Read at most `limit` bytes; `None` if the reader has more
+fn read_bounded<R: Read>(reader: R, limit: u64) -> io::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    reader.take(limit.saturating_add(1)).read_to_end(&mut data)?;
+    Ok((data.len() as u64 <= limit).then_some(data))
+}
+
+fn ratio(expanded: u64, stored: u64) -> f64 {
+    (expanded as f64 / stored.max(1) as f64 * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o640);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *data).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn expand_file(name: &str, data: &[u8], options: &ArchiveOptions) -> (FileInfo, Vec<FileInfo>) {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(name);
+        std::fs::write(&path, data).unwrap();
+        let engine = EngineOptions::default();
+        let mut info = engine::analyze_path(&path, &engine).unwrap();
+        let children = expand_path(&path, &mut info, &engine, options).unwrap();
+        (info, children)
+    }
+
+    #[test]
+    fn test_zip_entries_and_nesting() {
+        let inner = zip_bytes(&[("deep.txt", b"deep")]);
+        let outer = zip_bytes(&[("a.txt", b"hello world"), ("dir/inner.zip", &inner)]);
+        let (info, children) = expand_file("outer.zip", &outer, &ArchiveOptions::default());
+
+        let paths: Vec<&str> = children.iter()
+            .map(|c| c.path.rsplit_once("outer.zip").unwrap().1)
+            .collect();
+        assert_eq!(paths, vec!["!/a.txt", "!/dir/inner.zip", "!/dir/inner.zip!/deep.txt"]);
+
+        let archive = info.archive.unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Zip);
+        assert_eq!(archive.entries, 2);
+        assert_eq!(children[0].line_count, Some(1));
+        assert!(children[0].checksum.is_some());
+        let entry = children[0].archive_entry.as_ref().unwrap();
+        assert_eq!(entry.depth, 1);
+        assert!(entry.compressed_size.is_some());
+        assert_eq!(children[2].archive_entry.as_ref().unwrap().depth, 2);
+        assert_eq!(children[1].archive.as_ref().unwrap().entries, 1);
+
+        let shallow = ArchiveOptions { max_depth: 1, ..Default::default() };
+        let (_, children) = expand_file("outer.zip", &outer, &shallow);
+        assert_eq!(children.len(), 2);
+        assert!(children[1].archive.is_none());
+    }
+
+    #[test]
+    fn test_tar_gz_entries() {
+        let data = tar_gz_bytes(&[("src/main.rs", b"fn main() {}\n"), ("README", b"read me")]);
+        let (info, children) = expand_file("bundle.tar.gz", &data, &ArchiveOptions::default());
+
+        assert_eq!(info.archive.as_ref().unwrap().format, ArchiveFormat::TarGz);
+        assert_eq!(children.len(), 2);
+        assert!(children[0].path.ends_with("bundle.tar.gz!/src/main.rs"));
+        assert_eq!(children[0].file_type, "rs");
+        assert_eq!(children[0].permissions.as_deref(), Some("640"));
+        assert_eq!(children[0].archive_entry.as_ref().unwrap().compressed_size, None);
+    }
+
+    #[test]
+    fn test_bomb_guards() {
+        let zeros = vec![0u8; 1024 * 1024];
+        let bomb = zip_bytes(&[("zeros.bin", &zeros), ("after.txt", b"x")]);
+
+        let (info, children) = expand_file("bomb.zip", &bomb, &ArchiveOptions::default());
+        let entry = children[0].archive_entry.as_ref().unwrap();
+        assert_eq!(entry.skipped.as_deref(), Some("ratio limit"));
+        assert!(children[0].checksum.is_none());
+        assert_eq!(children.len(), 2);
+        assert!(info.archive.unwrap().incomplete.is_none());
+
+        let small = ArchiveOptions { max_total_size: 1000, max_ratio: f64::MAX, ..Default::default() };
+        let (info, children) = expand_file("bomb.zip", &bomb, &small);
+        assert!(children.is_empty());
+        assert_eq!(info.archive.unwrap().incomplete.as_deref(), Some("total size limit"));
+
+        let few = ArchiveOptions { max_entries: 1, ..Default::default() };
+        let (info, children) = expand_file("bomb.zip", &bomb, &few);
+        assert_eq!(children.len(), 1);
+        assert_eq!(info.archive.unwrap().incomplete.as_deref(), Some("entry limit"));
+
+        let gz = tar_gz_bytes(&[("zeros.bin", &zeros)]);
+        let (info, _) = expand_file("zeros.tgz", &gz, &ArchiveOptions::default());
+        assert_eq!(info.archive.unwrap().incomplete.as_deref(), Some("ratio limit"));
+    }
+
+    #[test]
+    fn test_corrupt_archive() {
+        let (info, children) = expand_file("broken.zip", b"PK\x03\x04 not really", &ArchiveOptions::default());
+        assert!(children.is_empty());
+        assert!(info.archive.unwrap().incomplete.unwrap().starts_with("unreadable"));
+    }
+}
@@ -0,0 +1,224 @@
+//! Permission Audit Module
+//!
+//! Runs over the analyzed files (collected, or one at a time when streaming)
+//! and the directories the walker flagged. Lists what a reviewer looks for
+//! first: paths anyone can write to, setuid/setgid files, files whose owner
+//! has no account, and how much of the tree each owner holds.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::Metadata;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use diamond_drill::engine::FileInfo;
+use diamond_drill::ownership::Ownership;
+
+/// A file or directory with the world-writable bit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WritablePath {
+    pub path: String,
+    pub mode: String,
+    pub directory: bool,
+    /// Sticky directories (like `/tmp`) only let owners delete their entries
+    pub sticky: bool,
+}
+
+/// A file that runs with its owner's or group's privileges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivilegedFile {
+    pub path: String,
+    pub mode: String,
+    /// Owner name, or the numeric uid
+    pub owner: String,
+    pub setuid: bool,
+    pub setgid: bool,
+}
+
+/// A file whose uid has no account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrphanFile {
+    pub path: String,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Files and bytes held by one owner; a hard-linked inode counts once
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnerUsage {
+    pub uid: u32,
+    #[serde(default)]
+    pub user: Option<String>,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PermissionAudit {
+    /// World-writable files and directories, by path
+    #[serde(default)]
+    pub world_writable: Vec<WritablePath>,
+    #[serde(default)]
+    pub setuid: Vec<PrivilegedFile>,
+    #[serde(default)]
+    pub unknown_owners: Vec<OrphanFile>,
+    /// Disk usage per owner, largest first
+    #[serde(default)]
+    pub owners: Vec<OwnerUsage>,
+}
+
+impl PermissionAudit {
+    /// Findings worth a warning (owner totals are informational)
+    pub fn findings(&self) -> usize {
+        self.world_writable.len() + self.setuid.len() + self.unknown_owners.len()
+    }
+}
+
+/// The walker's record of a world-writable directory; `None` otherwise
+#[cfg(unix)]
+pub fn writable_dir(path: &Path, metadata: &Metadata) -> Option<WritablePath> {
+    let ownership = Ownership::from_metadata(metadata);
+    ownership.world_writable.then(|| WritablePath {
+        path: path.display().to_string(),
+        mode: ownership.mode,
+        directory: true,
+        sticky: ownership.sticky,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn writable_dir(_path: &Path, _metadata: &Metadata) -> Option<WritablePath> {
+    None
+}
+
+/// Audit analyzed files; archive entries carry no ownership and are ignored
+pub fn audit(files: &[FileInfo], writable_dirs: Vec<WritablePath>) -> PermissionAudit {
+    let mut totals = AuditTotals::default();
+    for file in files {
+        totals.add(file);
+    }
+    totals.report(writable_dirs)
+}
+
+/// Running audit over analyzed files, for callers that do not keep them
+#[derive(Debug, Default)]
+pub struct AuditTotals {
+    audit: PermissionAudit,
+    owners: BTreeMap<u32, OwnerUsage>,
+    /// (device, inode) of hard-linked files already counted
+    seen: HashSet<(u64, u64)>,
+}
+
+impl AuditTotals {
+    pub fn add(&mut self, file: &FileInfo) {
+        let Some(owner) = file.ownership.as_ref() else {
+            return;
+        };
+        if owner.world_writable {
+            self.audit.world_writable.push(WritablePath {
+                path: file.path.clone(),
+                mode: owner.mode.clone(),
+                directory: false,
+                sticky: owner.sticky,
+            });
+        }
+        if owner.setuid || owner.setgid {
+            self.audit.setuid.push(PrivilegedFile {
+                path: file.path.clone(),
+                mode: owner.mode.clone(),
+                owner: owner.owner_label(),
+                setuid: owner.setuid,
+                setgid: owner.setgid,
+            });
+        }
+        if owner.user.is_none() {
+            self.audit.unknown_owners.push(OrphanFile {
+                path: file.path.clone(),
+                uid: owner.uid,
+                gid: owner.gid,
+            });
+        }
+        if file.storage.as_ref().is_some_and(|s| s.links > 1 && !self.seen.insert((s.dev, s.inode))) {
+            return;
+        }
+        let usage = self.owners.entry(owner.uid).or_insert_with(|| OwnerUsage {
+            uid: owner.uid,
+            user: owner.user.clone(),
+            files: 0,
+            bytes: 0,
+        });
+        usage.files += 1;
+        usage.bytes += file.size;
+    }
+
+    /// Final audit, with the world-writable directories the walker flagged
+    pub fn report(self, writable_dirs: Vec<WritablePath>) -> PermissionAudit {
+        let mut audit = self.audit;
+        audit.world_writable.extend(writable_dirs);
+        audit.world_writable.sort_by(|a, b| a.path.cmp(&b.path));
+        audit.setuid.sort_by(|a, b| a.path.cmp(&b.path));
+        audit.unknown_owners.sort_by(|a, b| a.path.cmp(&b.path));
+        audit.owners = self.owners.into_values().collect();
+        audit.owners.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.uid.cmp(&b.uid)));
+        audit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer;
+    use crate::config::Config;
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_permission_audit() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir(root.join("drop")).unwrap();
+        fs::set_permissions(root.join("drop"), Permissions::from_mode(0o1777)).unwrap();
+        fs::write(root.join("drop/note.txt"), "hi").unwrap();
+        fs::set_permissions(root.join("drop/note.txt"), Permissions::from_mode(0o666)).unwrap();
+        fs::write(root.join("helper"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(root.join("helper"), Permissions::from_mode(0o4755)).unwrap();
+        fs::write(root.join("plain.txt"), "12345").unwrap();
+        fs::hard_link(root.join("plain.txt"), root.join("plain-link.txt")).unwrap();
+
+        let results = analyzer::analyze(root.to_str().unwrap(), &Config::default()).await.unwrap();
+        let audit = &results.audit;
+
+        let writable: Vec<_> = audit.world_writable.iter()
+            .map(|w| (w.path.strip_prefix(root.to_str().unwrap()).unwrap(), w.directory, w.sticky))
+            .collect();
+        assert_eq!(writable, [("/drop", true, true), ("/drop/note.txt", false, false)]);
+
+        assert_eq!(audit.setuid.len(), 1);
+        assert!(audit.setuid[0].path.ends_with("helper"));
+        assert_eq!(audit.setuid[0].mode, "4755");
+        assert!(audit.setuid[0].setuid && !audit.setuid[0].setgid);
+
+        // Every file belongs to the user running the tests; the hard link
+        // adds no usage
+        assert_eq!(audit.owners.len(), 1);
+        assert_eq!(audit.owners[0].files, 3);
+        assert_eq!(audit.owners[0].bytes, 2 + 10 + 5);
+        assert_eq!(audit.findings(), 3 + audit.unknown_owners.len());
+    }
+
+    #[test]
+    fn test_unknown_owner() {
+        let mut file = diamond_drill::engine::analyze_bytes(&[0; 42], "/srv/orphan", &Default::default());
+        file.ownership = Some(Ownership::new(4_000_000_001, 100, 0o100644));
+
+        let audit = audit(&[file], Vec::new());
+        assert_eq!(audit.unknown_owners, [OrphanFile {
+            path: "/srv/orphan".to_string(),
+            uid: 4_000_000_001,
+            gid: 100,
+        }]);
+        assert_eq!(audit.owners[0].user, None);
+        assert_eq!(audit.owners[0].bytes, 42);
+    }
+}
@@ -44,14 +44,12 @@ impl Environment {
             env::var("APP_ENV"),
         ];
 
-        for indicator in env_indicators.iter() {
-            if let Ok(val) = indicator {
-                match val.to_lowercase().as_str() {
-                    "production" | "prod" => return Self::Production,
-                    "staging" | "stage" => return Self::Staging,
-                    "development" | "dev" | "local" => return Self::Development,
-                    _ => continue,
-                }
+        for val in env_indicators.iter().flatten() {
+            match val.to_lowercase().as_str() {
+                "production" | "prod" => return Self::Production,
+                "staging" | "stage" => return Self::Staging,
+                "development" | "dev" | "local" => return Self::Development,
+                _ => continue,
             }
         }
 
@@ -0,0 +1,163 @@
+//! Budget Module
+//!
+//! File and time budgets for a scan. The walker stops queueing files once
+//! `[analysis] max_files` have been taken or `time_budget` seconds have
+//! passed, and workers drop queued files after the deadline, listing each
+//! under `skipped`; the report is then marked `partial` with the limit that
+//! ran out. Files already being read when the deadline passes are finished.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::AnalysisConfig;
+
+/// The budget that cut a scan short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    MaxFiles,
+    TimeBudget,
+}
+
+impl std::fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BudgetLimit::MaxFiles => "max_files",
+            BudgetLimit::TimeBudget => "time_budget",
+        })
+    }
+}
+
+/// Budgets of one scan, shared by the walker and workers
+#[derive(Debug)]
+pub struct Budget {
+    max_files: Option<usize>,
+    deadline: Option<Instant>,
+    exhausted: OnceLock<BudgetLimit>,
+}
+
+impl Budget {
+    /// Start the clock for `config`'s budgets
+    pub fn start(config: &AnalysisConfig) -> Self {
+        Self {
+            max_files: config.max_files,
+            deadline: config.time_budget.map(|secs| Instant::now() + Duration::from_secs(secs)),
+            exhausted: OnceLock::new(),
+        }
+    }
+
+    /// Whether the walker may queue its `taken + 1`th file
+    pub fn admit(&self, taken: usize) -> bool {
+        if self.max_files.is_some_and(|max| taken >= max) {
+            self.exhaust(BudgetLimit::MaxFiles);
+            return false;
+        }
+        !self.expired()
+    }
+
+    /// Whether the time budget has run out
+    pub fn expired(&self) -> bool {
+        let expired = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if expired {
+            self.exhaust(BudgetLimit::TimeBudget);
+        }
+        expired
+    }
+
+    /// The first limit that ran out, if any
+    pub fn exhausted(&self) -> Option<BudgetLimit> {
+        self.exhausted.get().copied()
+    }
+
+    fn exhaust(&self, limit: BudgetLimit) {
+        let _ = self.exhausted.set(limit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer;
+    use crate::config::Config;
+    use crate::scan_errors::IssueReason;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_budget_limits() {
+        let mut config = AnalysisConfig::default();
+        let unlimited = Budget::start(&config);
+        assert!(unlimited.admit(1_000_000));
+        assert_eq!(unlimited.exhausted(), None);
+
+        config.max_files = Some(2);
+        config.time_budget = Some(0);
+        let budget = Budget::start(&config);
+        assert!(!budget.admit(2));
+        assert!(budget.expired());
+        // The first limit hit is the one reported
+        assert_eq!(budget.exhausted(), Some(BudgetLimit::MaxFiles));
+    }
+
+    #[tokio::test]
+    async fn test_partial_reports() {
+        let temp = tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(temp.path().join(name), name).unwrap();
+        }
+        fs::create_dir_all(temp.path().join("one/two")).unwrap();
+        fs::write(temp.path().join("one/mid.txt"), "mid").unwrap();
+        fs::write(temp.path().join("one/two/deep.txt"), "deep").unwrap();
+        let source = temp.path().to_str().unwrap();
+
+        let mut config = Config::default();
+        let complete = analyzer::analyze(source, &config).await.unwrap();
+        assert!(!complete.partial);
+        assert_eq!(complete.limit_hit, None);
+
+        config.analysis.max_files = Some(2);
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        assert!(results.partial);
+        assert_eq!(results.limit_hit, Some(BudgetLimit::MaxFiles));
+        assert_eq!(results.total_files, 2);
+
+        config.analysis.max_files = None;
+        config.analysis.time_budget = Some(0);
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        assert_eq!(results.limit_hit, Some(BudgetLimit::TimeBudget));
+        assert_eq!(results.total_files, 0);
+
+        // Depth limits prune without making the report partial
+        config.analysis.time_budget = None;
+        config.analysis.max_depth = Some(2);
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        assert!(!results.partial);
+        assert_eq!(results.total_files, 4);
+        let too_deep: Vec<_> = results.skipped.iter()
+            .filter(|s| s.reason == IssueReason::TooDeep)
+            .map(|s| s.path.strip_prefix(source).unwrap())
+            .collect();
+        assert_eq!(too_deep, ["/one/two/deep.txt"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_one_file_system() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("local.txt"), "here").unwrap();
+        // procfs is always a filesystem of its own
+        std::os::unix::fs::symlink("/proc/self/fdinfo", temp.path().join("proc")).unwrap();
+        let source = temp.path().to_str().unwrap();
+
+        let mut config = Config::default();
+        config.analysis.one_file_system = true;
+        let results = analyzer::analyze(source, &config).await.unwrap();
+
+        assert_eq!(results.total_files, 1);
+        assert_eq!(results.skipped.len(), 1);
+        assert_eq!(results.skipped[0].reason, IssueReason::OtherFilesystem);
+        assert!(results.skipped[0].path.ends_with("/proc"));
+    }
+}
@@ -0,0 +1,371 @@
+//! Analysis Cache Module
+//!
+//! Persistent per-source index of analyzed files. Entries are keyed by
+//! (device, inode, size, mtime, ctime) and the file name, so any change to a
+//! file's content misses the cache while moves and untouched files hit it.
+//! The name is part of the key because the file type and language come from
+//! it, which also keeps hard links under different names apart. Timestamps,
+//! permissions and ownership are read again on every hit. The index lives
+//! under the user cache directory, never inside the source.
+
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use diamond_drill::engine::{self, EngineOptions, FileInfo};
+use diamond_drill::hasher::{self, HashAlgorithm};
+
+use crate::config::Config;
+
+/// Bump when `FileInfo` gains fields the analyzer fills from file content
+const CACHE_FORMAT: u32 = 13;
+
+/// Identity of a file version on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub dev: u64,
+    pub ino: u64,
+    pub size: u64,
+    pub mtime: i64,
+    pub mtime_nsec: i64,
+    pub ctime: i64,
+    pub ctime_nsec: i64,
+}
+
+impl CacheKey {
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Self {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            size: metadata.size(),
+            mtime: metadata.mtime(),
+            mtime_nsec: metadata.mtime_nsec(),
+            ctime: metadata.ctime(),
+            ctime_nsec: metadata.ctime_nsec(),
+        }
+    }
+
+    /// Without inodes or ctime the key falls back to size and mtime
+    #[cfg(not(unix))]
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let mtime = metadata.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        Self {
+            dev: 0,
+            ino: 0,
+            size: metadata.len(),
+            mtime: mtime.as_secs() as i64,
+            mtime_nsec: i64::from(mtime.subsec_nanos()),
+            ctime: 0,
+            ctime_nsec: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: CacheKey,
+    info: FileInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    format: u32,
+    fingerprint: String,
+    source: String,
+    entries: Vec<CacheEntry>,
+}
+
+/// On-disk index shared by the analysis workers
+pub struct AnalysisCache {
+    path: PathBuf,
+    source: String,
+    fingerprint: String,
+    previous: HashMap<(CacheKey, String), FileInfo>,
+    current: Mutex<HashMap<(CacheKey, String), FileInfo>>,
+    hits: AtomicUsize,
+}
+
+impl AnalysisCache {
+    /// Default index location for a source: `<cache dir>/<source digest>.json`
+    pub fn location(source: &str, config: &Config) -> PathBuf {
+        let dir = config.cache.dir.as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(default_cache_dir);
+
+        let canonical = Path::new(source).canonicalize()
+            .unwrap_or_else(|_| PathBuf::from(source));
+        let digests = hasher::hash_reader(
+            canonical.to_string_lossy().as_bytes(),
+            &[HashAlgorithm::Sha256],
+        )
+        .unwrap_or_default();
+        let name = digests.get(&HashAlgorithm::Sha256)
+            .map(|d| d[..32].to_string())
+            .unwrap_or_else(|| "default".to_string());
+
+        dir.join(format!("{}.json", name))
+    }
+
+    /// Load the index at `path`; a missing, stale or corrupt index starts empty
+    pub fn open(path: PathBuf, source: &str, config: &Config) -> Self {
+        let fingerprint = fingerprint(config);
+        let previous = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<CacheFile>(&bytes) {
+                Ok(file) if file.format == CACHE_FORMAT && file.fingerprint == fingerprint => {
+                    debug!("Loaded {} cache entries from {}", file.entries.len(), path.display());
+                    file.entries.into_iter()
+                        .map(|e| ((e.key, file_name(Path::new(&e.info.path))), e.info))
+                        .collect()
+                }
+                Ok(_) => {
+                    info!("Analysis settings changed, rebuilding cache");
+                    HashMap::new()
+                }
+                Err(e) => {
+                    warn!("Ignoring unreadable cache {}: {}", path.display(), e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            source: source.to_string(),
+            fingerprint,
+            previous,
+            current: Mutex::new(HashMap::new()),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Cached record for an unchanged file, re-pointed at its current path
+    /// with its metadata fields refreshed from `metadata`
+    pub fn lookup(
+        &self,
+        key: &CacheKey,
+        path: &Path,
+        metadata: &Metadata,
+        options: &EngineOptions,
+    ) -> Option<FileInfo> {
+        let mut info = self.previous.get(&(*key, file_name(path)))?.clone();
+        info.path = path.display().to_string();
+        engine::apply_metadata(&mut info, path, metadata, options);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        self.store(*key, &info);
+        Some(info)
+    }
+
+    pub fn store(&self, key: CacheKey, info: &FileInfo) {
+        if let Ok(mut current) = self.current.lock() {
+            current.insert((key, file_name(Path::new(&info.path))), info.clone());
+        }
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Persist the entries seen during this run (files that vanished are dropped)
+    pub fn save(self) -> io::Result<()> {
+        let current = self.current.into_inner().unwrap_or_else(|e| e.into_inner());
+        let mut entries: Vec<CacheEntry> = current
+            .into_iter()
+            .map(|((key, _), info)| CacheEntry { key, info })
+            .collect();
+        entries.sort_by(|a, b| a.info.path.cmp(&b.info.path));
+
+        let file = CacheFile {
+            format: CACHE_FORMAT,
+            fingerprint: self.fingerprint,
+            source: self.source,
+            entries,
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&file)?)?;
+        fs::rename(&tmp, &self.path)?;
+        debug!("Saved {} cache entries to {}", file.entries.len(), self.path.display());
+        Ok(())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Settings that change what the analyzer records for a file
+fn fingerprint(config: &Config) -> String {
+    format!(
+        "{}:{:?}:{}",
+        env!("CARGO_PKG_VERSION"),
+        config.analysis.hash_algorithms,
+        config.analysis.extended_attributes
+    )
+}
+
+fn default_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("diamond-drill")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer;
+    use tempfile::tempdir;
+
+    fn config_with_dir(dir: &Path) -> Config {
+        let mut config = Config::default();
+        config.cache.dir = Some(dir.display().to_string());
+        config
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_files_hit_cache() {
+        let source = tempdir().unwrap();
+        let cache_dir = tempdir().unwrap();
+        fs::write(source.path().join("a.txt"), "alpha").unwrap();
+        fs::write(source.path().join("b.txt"), "beta").unwrap();
+
+        let src = source.path().to_str().unwrap();
+        let config = config_with_dir(cache_dir.path());
+        let path = AnalysisCache::location(src, &config);
+        assert!(path.starts_with(cache_dir.path()));
+
+        let cache = AnalysisCache::open(path.clone(), src, &config);
+        let (first, cache) = analyzer::analyze_cached(src, &config, Some(cache)).await.unwrap();
+        assert_eq!(cache.as_ref().unwrap().hits(), 0);
+        cache.unwrap().save().unwrap();
+
+        fs::write(source.path().join("b.txt"), "beta, edited").unwrap();
+
+        let cache = AnalysisCache::open(path, src, &config);
+        let (second, cache) = analyzer::analyze_cached(src, &config, Some(cache)).await.unwrap();
+        assert_eq!(cache.unwrap().hits(), 1);
+        assert_eq!(first.files[0].checksum, second.files[0].checksum);
+        assert_ne!(first.files[1].checksum, second.files[1].checksum);
+    }
+
+    #[tokio::test]
+    async fn test_cached_entries_keep_sniffed_kind() {
+        let source = tempdir().unwrap();
+        let cache_dir = tempdir().unwrap();
+        fs::write(source.path().join("deploy"), "#!/bin/sh\necho hi\n").unwrap();
+
+        let src = source.path().to_str().unwrap();
+        let config = config_with_dir(cache_dir.path());
+        let path = AnalysisCache::location(src, &config);
+        let cache = AnalysisCache::open(path.clone(), src, &config);
+        let (cold, cache) = analyzer::analyze_cached(src, &config, Some(cache)).await.unwrap();
+        cache.unwrap().save().unwrap();
+
+        let cache = AnalysisCache::open(path, src, &config);
+        let (warm, cache) = analyzer::analyze_cached(src, &config, Some(cache)).await.unwrap();
+        assert_eq!(cache.unwrap().hits(), 1);
+        assert_eq!(cold.files[0].content_type.kind.as_deref(), Some("sh"));
+        assert_eq!(warm.files[0].content_type, cold.files[0].content_type);
+    }
+
+    #[test]
+    fn test_hits_refresh_metadata() {
+        let cache_dir = tempdir().unwrap();
+        let file = cache_dir.path().join("a.txt");
+        fs::write(&file, "alpha").unwrap();
+        let metadata = fs::metadata(&file).unwrap();
+        let key = CacheKey::from_metadata(&metadata);
+        let options = EngineOptions::default();
+
+        let config = config_with_dir(cache_dir.path());
+        let cache = AnalysisCache::open(cache_dir.path().join("index.json"), "src", &config);
+        let mut stale = engine::analyze_path(&file, &options).unwrap();
+        stale.accessed = Some("1970-01-01T00:00:00Z".to_string());
+        stale.permissions = Some("000".to_string());
+        cache.store(key, &stale);
+        cache.save().unwrap();
+
+        let reopened = AnalysisCache::open(cache_dir.path().join("index.json"), "src", &config);
+        let hit = reopened.lookup(&key, &file, &metadata, &options).unwrap();
+        let fresh = engine::analyze_with_metadata(&file, &metadata, &options).unwrap();
+        assert_eq!(hit.accessed, fresh.accessed);
+        assert_eq!(hit.permissions, fresh.permissions);
+        assert_eq!(hit.checksum, stale.checksum);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hard_links_keep_their_own_names() {
+        let source = tempdir().unwrap();
+        let cache_dir = tempdir().unwrap();
+        fs::write(source.path().join("build.py"), "print('hi')\n").unwrap();
+        fs::hard_link(source.path().join("build.py"), source.path().join("build.txt")).unwrap();
+
+        let src = source.path().to_str().unwrap();
+        let config = config_with_dir(cache_dir.path());
+        let path = AnalysisCache::location(src, &config);
+        let cache = AnalysisCache::open(path.clone(), src, &config);
+        let (cold, cache) = analyzer::analyze_cached(src, &config, Some(cache)).await.unwrap();
+        cache.unwrap().save().unwrap();
+
+        let cache = AnalysisCache::open(path, src, &config);
+        let (warm, cache) = analyzer::analyze_cached(src, &config, Some(cache)).await.unwrap();
+        assert_eq!(cache.unwrap().hits(), 2);
+        let types: Vec<_> = warm.files.iter().map(|f| f.file_type.as_str()).collect();
+        assert_eq!(types, ["py", "txt"]);
+        assert_eq!(warm.files[0].sloc, cold.files[0].sloc);
+        assert!(warm.files[0].sloc.is_some());
+        assert!(warm.files[1].sloc.is_none());
+    }
+
+    #[test]
+    fn test_settings_change_invalidates() {
+        let cache_dir = tempdir().unwrap();
+        let file = cache_dir.path().join("a.txt");
+        fs::write(&file, "alpha").unwrap();
+        let metadata = fs::metadata(&file).unwrap();
+        let key = CacheKey::from_metadata(&metadata);
+        let options = EngineOptions::default();
+
+        let config = config_with_dir(cache_dir.path());
+        let path = cache_dir.path().join("index.json");
+        let cache = AnalysisCache::open(path.clone(), "src", &config);
+        let info: FileInfo = serde_json::from_value(serde_json::json!({
+            "path": file.display().to_string(),
+            "size": 5,
+            "file_type": "txt",
+            "modified": null,
+            "checksum": null
+        }))
+        .unwrap();
+        cache.store(key, &info);
+        cache.save().unwrap();
+
+        let reopened = AnalysisCache::open(path.clone(), "src", &config);
+        assert!(reopened.lookup(&key, &file, &metadata, &options).is_some());
+
+        let mut changed = config.clone();
+        changed.analysis.hash_algorithms = vec![HashAlgorithm::Sha512];
+        let invalidated = AnalysisCache::open(path, "src", &changed);
+        assert!(invalidated.lookup(&key, &file, &metadata, &options).is_none());
+    }
+}
@@ -7,7 +7,11 @@ use serde::{Deserialize, Serialize};
 use anyhow::Result;
 use tracing::debug;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use diamond_drill::archive::ArchiveOptions;
+use diamond_drill::engine::EngineOptions;
+use diamond_drill::hasher::HashAlgorithm;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub analysis: AnalysisConfig,
@@ -17,6 +21,15 @@ pub struct Config {
 
     #[serde(default)]
     pub security: SecurityConfig,
+
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+
+    #[serde(default)]
+    pub archives: ArchiveConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,11 +40,59 @@ pub struct AnalysisConfig {
     #[serde(default = "default_include_patterns")]
     pub include_patterns: Vec<String>,
 
-    #[serde(default)]
+    #[serde(default = "default_exclude_patterns")]
     pub exclude_patterns: Vec<String>,
 
+    /// Honor `.gitignore` and `.diamondignore` files while walking
+    #[serde(default = "default_true")]
+    pub respect_ignore_files: bool,
+
+    /// Follow symlinks that resolve; dangling and looping links never are
     #[serde(default = "default_true")]
     pub follow_symlinks: bool,
+
+    /// Set at runtime while the source is held under a read-only lock;
+    /// symlinks escaping the source are then never followed
+    #[serde(skip)]
+    pub ro_locked: bool,
+
+    /// Metadata/content worker threads; 0 uses one per CPU
+    #[serde(default)]
+    pub workers: usize,
+
+    /// Group identical files into `duplicate_groups` after analysis
+    #[serde(default = "default_true")]
+    pub find_duplicates: bool,
+
+    /// Largest files and directories listed in the usage report
+    #[serde(default = "default_top_n")]
+    pub top_n: usize,
+
+    /// Digests computed for every file; empty disables hashing
+    #[serde(default = "default_hash_algorithms")]
+    pub hash_algorithms: Vec<HashAlgorithm>,
+
+    /// Record extended attributes (SELinux labels, file capabilities, ACLs);
+    /// off by default since it costs extra system calls per file
+    #[serde(default)]
+    pub extended_attributes: bool,
+
+    /// Deepest level walked below the source (1 = its direct children only);
+    /// unlimited when unset
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+
+    /// Stay on the source's filesystem, skipping mount points such as `/proc`
+    #[serde(default)]
+    pub one_file_system: bool,
+
+    /// Stop after this many files and mark the report partial
+    #[serde(default)]
+    pub max_files: Option<usize>,
+
+    /// Stop after this many seconds and mark the report partial
+    #[serde(default)]
+    pub time_budget: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,18 +119,105 @@ pub struct SecurityConfig {
     pub allowed_paths: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Reuse results for unchanged files between CLI runs
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Index directory; defaults to `$XDG_CACHE_HOME/diamond-drill`
+    #[serde(default)]
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    /// Scan text files for leaked credentials after analysis
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Rule file; relative paths resolve against this config file.
+    /// Defaults to the built-in `rules/secrets.toml`
+    #[serde(default)]
+    pub rules: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    /// List zip/jar/tar/tar.gz entries as virtual `archive!/entry` files
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Nesting levels opened (archives inside archives)
+    #[serde(default = "default_archive_depth")]
+    pub max_depth: u32,
+
+    /// Bytes decompressed per top-level archive
+    #[serde(default = "default_archive_total_size")]
+    pub max_total_size: u64,
+
+    /// Largest uncompressed/compressed ratio before an entry is skipped
+    #[serde(default = "default_archive_ratio")]
+    pub max_ratio: f64,
+
+    /// Entries listed per top-level archive
+    #[serde(default = "default_archive_entries")]
+    pub max_entries: usize,
+}
+
 fn default_max_file_size() -> usize { 100 * 1024 * 1024 } // 100MB
+fn default_top_n() -> usize { 20 }
 fn default_include_patterns() -> Vec<String> { vec!["*".to_string()] }
+fn default_exclude_patterns() -> Vec<String> { vec![".git/".to_string()] }
+fn default_hash_algorithms() -> Vec<HashAlgorithm> { vec![HashAlgorithm::Sha256] }
 fn default_format() -> String { "json".to_string() }
 fn default_true() -> bool { true }
+fn default_archive_depth() -> u32 { ArchiveOptions::default().max_depth }
+fn default_archive_total_size() -> u64 { ArchiveOptions::default().max_total_size }
+fn default_archive_ratio() -> f64 { ArchiveOptions::default().max_ratio }
+fn default_archive_entries() -> usize { ArchiveOptions::default().max_entries }
+
+impl AnalysisConfig {
+    /// Per-file engine settings derived from this section
+    pub fn engine_options(&self) -> EngineOptions {
+        EngineOptions {
+            max_file_size: self.max_file_size as u64,
+            hash_algorithms: self.hash_algorithms.clone(),
+            xattrs: self.extended_attributes,
+        }
+    }
+}
+
+impl ArchiveConfig {
+    /// Expansion limits, or `None` when archive listing is off
+    pub fn archive_options(&self) -> Option<ArchiveOptions> {
+        self.enabled.then_some(ArchiveOptions {
+            max_depth: self.max_depth,
+            max_total_size: self.max_total_size,
+            max_ratio: self.max_ratio,
+            max_entries: self.max_entries,
+        })
+    }
+}
 
 impl Default for AnalysisConfig {
     fn default() -> Self {
         Self {
             max_file_size: default_max_file_size(),
             include_patterns: default_include_patterns(),
-            exclude_patterns: vec![],
+            exclude_patterns: default_exclude_patterns(),
+            respect_ignore_files: true,
             follow_symlinks: true,
+            ro_locked: false,
+            workers: 0,
+            find_duplicates: true,
+            top_n: default_top_n(),
+            hash_algorithms: default_hash_algorithms(),
+            extended_attributes: false,
+            max_depth: None,
+            one_file_system: false,
+            max_files: None,
+            time_budget: None,
         }
     }
 }
@@ -94,12 +242,32 @@ impl Default for SecurityConfig {
     }
 }
 
-impl Default for Config {
+impl Default for CacheConfig {
     fn default() -> Self {
         Self {
-            analysis: AnalysisConfig::default(),
-            export: ExportConfig::default(),
-            security: SecurityConfig::default(),
+            enabled: true,
+            dir: None,
+        }
+    }
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: None,
+        }
+    }
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_depth: default_archive_depth(),
+            max_total_size: default_archive_total_size(),
+            max_ratio: default_archive_ratio(),
+            max_entries: default_archive_entries(),
         }
     }
 }
@@ -111,7 +279,10 @@ impl Config {
         if path.exists() {
             debug!("Loading config from: {}", path.display());
             let content = tokio::fs::read_to_string(path).await?;
-            let config: Config = toml::from_str(&content)?;
+            let mut config: Config = toml::from_str(&content)?;
+            if let (Some(rules), Some(dir)) = (&config.secrets.rules, path.parent()) {
+                config.secrets.rules = Some(dir.join(rules).to_string_lossy().into_owned());
+            }
             Ok(config)
         } else {
             debug!("Config file not found, using defaults");
@@ -151,4 +322,34 @@ pretty_print = false
         assert_eq!(config.export.format, "yaml");
         assert!(!config.export.pretty_print);
     }
+
+    #[tokio::test]
+    async fn test_load_hash_algorithms() {
+        let mut temp = NamedTempFile::new().unwrap();
+        writeln!(temp, r#"
+[analysis]
+hash_algorithms = ["sha256", "sha512"]
+"#).unwrap();
+
+        let config = Config::load(temp.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(
+            config.analysis.hash_algorithms,
+            vec![HashAlgorithm::Sha256, HashAlgorithm::Sha512]
+        );
+        assert_eq!(Config::default().analysis.hash_algorithms, vec![HashAlgorithm::Sha256]);
+    }
+
+    #[tokio::test]
+    async fn test_secrets_rules_relative_to_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("diamond.toml");
+        std::fs::write(&path, "[secrets]\nrules = \"rules/custom.toml\"\n").unwrap();
+
+        let config = Config::load(path.to_str().unwrap()).await.unwrap();
+        assert_eq!(
+            config.secrets.rules.as_deref(),
+            Some(dir.path().join("rules/custom.toml").to_str().unwrap())
+        );
+        assert!(config.secrets.enabled);
+    }
 }
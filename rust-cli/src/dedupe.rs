@@ -0,0 +1,233 @@
+//! Duplicate Detection Module
+//!
+//! Runs after analysis over the collected `FileInfo` list. Candidates are
+//! narrowed in three passes: equal size, equal hash of the first few KiB,
+//! then equal full-content hash. Checksums already computed by the analyzer
+//! are reused, so a hashed run costs no extra reads; otherwise dedupe falls
+//! back to SHA-256. Each group records which algorithm its digest is from.
+//! Hard links to one inode are a single copy and never count as waste.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use diamond_drill::engine::FileInfo;
+use diamond_drill::hasher::{self, HashAlgorithm};
+
+/// Bytes read for the partial-hash pass
+const PARTIAL_HASH_LEN: u64 = 4096;
+
+/// Files with identical content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Full-content digest shared by every file in the group
+    pub hash: String,
+    /// Algorithm `hash` was computed with
+    #[serde(default = "default_algorithm")]
+    pub algorithm: HashAlgorithm,
+    /// Size of each copy
+    pub size: u64,
+    /// Bytes reclaimable by keeping a single copy
+    pub wasted_bytes: u64,
+    pub paths: Vec<String>,
+}
+
+/// Find groups of identical files, largest waste first
+pub fn find_duplicates(files: &[FileInfo]) -> Vec<DuplicateGroup> {
+    let mut by_size: BTreeMap<u64, Vec<&FileInfo>> = BTreeMap::new();
+    // Archive entries cannot be removed on their own, so they never count as waste
+    let mut candidates: Vec<&FileInfo> = files.iter()
+        .filter(|f| f.size > 0 && f.archive_entry.is_none())
+        .collect();
+    // Hard links share their bytes: keep one path per inode (listed in `hard_links`)
+    candidates.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut inodes = HashSet::new();
+    candidates.retain(|f| match &f.storage {
+        Some(s) if s.links > 1 => inodes.insert((s.dev, s.inode)),
+        _ => true,
+    });
+    for file in candidates {
+        by_size.entry(file.size).or_default().push(file);
+    }
+
+    let mut groups = Vec::new();
+    for (size, candidates) in by_size {
+        if candidates.len() < 2 {
+            continue;
+        }
+
+        // Reuse analyzer digests when every candidate has one of the same algorithm
+        let (algorithm, full_groups) = match common_algorithm(&candidates) {
+            Some(algorithm) => (
+                algorithm,
+                group_by(candidates, |f| f.hashes.get(&algorithm).cloned()),
+            ),
+            None => (
+                HashAlgorithm::Sha256,
+                group_by(candidates, |f| hash_path(&f.path, Some(PARTIAL_HASH_LEN)))
+                    .into_iter()
+                    .flat_map(|(_, partial)| group_by(partial, |f| hash_path(&f.path, None)))
+                    .collect(),
+            ),
+        };
+
+        for (hash, members) in full_groups {
+            let mut paths: Vec<String> = members.iter().map(|f| f.path.clone()).collect();
+            paths.sort();
+            groups.push(DuplicateGroup {
+                hash,
+                algorithm,
+                size,
+                wasted_bytes: size * (paths.len() as u64 - 1),
+                paths,
+            });
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        b.wasted_bytes.cmp(&a.wasted_bytes).then_with(|| a.paths.cmp(&b.paths))
+    });
+    groups
+}
+
+fn default_algorithm() -> HashAlgorithm {
+    HashAlgorithm::Sha256
+}
+
+/// First algorithm every candidate already has a digest for
+fn common_algorithm(candidates: &[&FileInfo]) -> Option<HashAlgorithm> {
+    let first = candidates.first()?;
+    first.hashes.keys()
+        .copied()
+        .find(|a| candidates.iter().all(|f| f.hashes.contains_key(a)))
+}
+
+/// Split files into groups sharing a key, dropping singletons and unreadable files
+fn group_by<F>(files: Vec<&FileInfo>, key: F) -> Vec<(String, Vec<&FileInfo>)>
+where
+    F: Fn(&FileInfo) -> Option<String>,
+{
+    let mut buckets: BTreeMap<String, Vec<&FileInfo>> = BTreeMap::new();
+    for file in files {
+        if let Some(k) = key(file) {
+            buckets.entry(k).or_default().push(file);
+        }
+    }
+    buckets.into_iter().filter(|(_, g)| g.len() > 1).collect()
+}
+
+/// SHA-256 of the first `limit` bytes of a file, or all of it
+fn hash_path(path: &str, limit: Option<u64>) -> Option<String> {
+    let result = File::open(Path::new(path)).and_then(|file| {
+        let reader: Box<dyn Read> = match limit {
+            Some(n) => Box::new(file.take(n)),
+            None => Box::new(file),
+        };
+        hasher::hash_reader(reader, &[HashAlgorithm::Sha256])
+    });
+
+    match result {
+        Ok(mut digests) => digests.remove(&HashAlgorithm::Sha256),
+        Err(e) => {
+            debug!("Dedupe cannot read {}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer;
+    use crate::config::Config;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_tree(root: &Path) {
+        let big = vec![7u8; 10_000];
+        let mut big_variant = big.clone();
+        *big_variant.last_mut().unwrap() = 8; // same size and prefix, different tail
+
+        fs::write(root.join("a.bin"), &big).unwrap();
+        fs::write(root.join("b.bin"), &big).unwrap();
+        fs::write(root.join("c.bin"), &big).unwrap();
+        fs::write(root.join("d.bin"), &big_variant).unwrap();
+        fs::write(root.join("x.txt"), "hello").unwrap();
+        fs::write(root.join("y.txt"), "hello").unwrap();
+        fs::write(root.join("z.txt"), "world").unwrap();
+        fs::write(root.join("empty1"), "").unwrap();
+        fs::write(root.join("empty2"), "").unwrap();
+    }
+
+    async fn check_groups(config: &Config) {
+        let temp = tempdir().unwrap();
+        write_tree(temp.path());
+
+        let results = analyzer::analyze(temp.path().to_str().unwrap(), config).await.unwrap();
+        let groups = find_duplicates(&results.files);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].paths.len(), 3);
+        assert_eq!(groups[0].wasted_bytes, 20_000);
+        assert!(groups[0].paths[0].ends_with("a.bin"));
+        assert_eq!(groups[1].wasted_bytes, 5);
+        assert_eq!(groups[1].algorithm, HashAlgorithm::Sha256);
+        assert_eq!(
+            groups[1].hash,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_from_checksums() {
+        check_groups(&Config::default()).await;
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_without_checksums() {
+        let mut config = Config::default();
+        config.analysis.hash_algorithms.clear();
+        check_groups(&config).await;
+    }
+
+    #[tokio::test]
+    async fn test_hard_links_are_not_duplicates() {
+        let temp = tempdir().unwrap();
+        let data = vec![3u8; 5000];
+        fs::write(temp.path().join("a.bin"), &data).unwrap();
+        fs::hard_link(temp.path().join("a.bin"), temp.path().join("b.bin")).unwrap();
+
+        let results = analyzer::analyze(temp.path().to_str().unwrap(), &Config::default()).await.unwrap();
+        assert_eq!(results.hard_links.len(), 1);
+        assert!(find_duplicates(&results.files).is_empty());
+
+        // A real copy is still a duplicate, counted once against the inode
+        fs::write(temp.path().join("c.bin"), &data).unwrap();
+        let results = analyzer::analyze(temp.path().to_str().unwrap(), &Config::default()).await.unwrap();
+        let groups = find_duplicates(&results.files);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].wasted_bytes, 5000);
+        assert_eq!(groups[0].paths.len(), 2);
+        assert!(groups[0].paths[0].ends_with("a.bin") && groups[0].paths[1].ends_with("c.bin"));
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_report_their_algorithm() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join("x.txt"), "hello").unwrap();
+        fs::write(temp.path().join("y.txt"), "hello").unwrap();
+
+        let mut config = Config::default();
+        config.analysis.hash_algorithms = vec![HashAlgorithm::Sha512];
+        let results = analyzer::analyze(temp.path().to_str().unwrap(), &config).await.unwrap();
+        let groups = find_duplicates(&results.files);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].algorithm, HashAlgorithm::Sha512);
+        assert_eq!(groups[0].hash.len(), 128);
+    }
+}
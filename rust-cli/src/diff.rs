@@ -0,0 +1,471 @@
+//! Report Diff Module
+//!
+//! Compares two exported `AnalysisResults` reports. Files are matched by
+//! their path relative to each report's source, so reports taken from a
+//! relocated copy of the same tree still line up. Content is compared with
+//! a digest algorithm both reports carry; without one, size and mtime decide.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use diamond_drill::engine::FileInfo;
+use diamond_drill::hasher::HashAlgorithm;
+use diamond_drill::timestamps;
+
+use crate::analyzer::AnalysisResults;
+
+/// Output format for `diamond diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffFormat {
+    Human,
+    Json,
+    Markdown,
+}
+
+/// A single difference between the reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub path: String,
+    /// Previous path of a moved file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiffTotals {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub moved: usize,
+    pub type_changed: usize,
+    pub unchanged: usize,
+    /// New total size minus old total size
+    pub size_delta: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffReport {
+    pub old_source: String,
+    pub new_source: String,
+    pub old_timestamp: String,
+    pub new_timestamp: String,
+    pub added: Vec<Change>,
+    pub removed: Vec<Change>,
+    pub modified: Vec<Change>,
+    pub moved: Vec<Change>,
+    pub type_changed: Vec<Change>,
+    pub totals: DiffTotals,
+}
+
+/// Load an exported report
+pub fn load_report(path: &str) -> Result<AnalysisResults> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read report {}", path))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("{} is not an analysis report", path))
+}
+
+/// Compare two reports
+pub fn diff_reports(old: &AnalysisResults, new: &AnalysisResults) -> DiffReport {
+    let old_files = index_by_relative_path(old);
+    let new_files = index_by_relative_path(new);
+    let algorithm = shared_algorithm(old, new);
+
+    let mut report = DiffReport {
+        old_source: old.source_path.clone(),
+        new_source: new.source_path.clone(),
+        old_timestamp: old.timestamp.clone(),
+        new_timestamp: new.timestamp.clone(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+        moved: Vec::new(),
+        type_changed: Vec::new(),
+        totals: DiffTotals::default(),
+    };
+
+    let mut removed: Vec<(&String, &FileInfo)> = Vec::new();
+    for (path, old_file) in &old_files {
+        match new_files.get(path) {
+            Some(new_file) => {
+                if type_of(old_file) != type_of(new_file) {
+                    report.type_changed.push(change(path, Some(old_file), Some(new_file)));
+                } else if is_modified(digest(old_file, algorithm), digest(new_file, algorithm), old_file, new_file) {
+                    report.modified.push(change(path, Some(old_file), Some(new_file)));
+                } else {
+                    report.totals.unchanged += 1;
+                }
+            }
+            None => removed.push((path, old_file)),
+        }
+    }
+
+    // Pair removed and added files that share content: those are moves
+    let mut added_by_hash: HashMap<&str, Vec<&String>> = HashMap::new();
+    for (path, new_file) in &new_files {
+        if old_files.contains_key(path) {
+            continue;
+        }
+        match move_digest(new_file, algorithm) {
+            Some(hash) => added_by_hash.entry(hash).or_default().push(path),
+            None => report.added.push(change(path, None, Some(new_file))),
+        }
+    }
+    for candidates in added_by_hash.values_mut() {
+        candidates.reverse(); // pop() hands out paths in sorted order
+    }
+
+    for (path, old_file) in removed {
+        let target = move_digest(old_file, algorithm)
+            .and_then(|hash| added_by_hash.get_mut(hash))
+            .and_then(|candidates| candidates.pop());
+        match target {
+            Some(new_path) => {
+                let mut moved = change(new_path, Some(old_file), Some(new_files[new_path]));
+                moved.from = Some(path.clone());
+                report.moved.push(moved);
+            }
+            None => report.removed.push(change(path, Some(old_file), None)),
+        }
+    }
+    for path in added_by_hash.into_values().flatten() {
+        report.added.push(change(path, None, Some(new_files[path])));
+    }
+    report.added.sort_by(|a, b| a.path.cmp(&b.path));
+
+    report.totals.added = report.added.len();
+    report.totals.removed = report.removed.len();
+    report.totals.modified = report.modified.len();
+    report.totals.moved = report.moved.len();
+    report.totals.type_changed = report.type_changed.len();
+    report.totals.size_delta = new.total_size as i64 - old.total_size as i64;
+
+    report
+}
+
+/// Render a diff in the requested format
+pub fn render(report: &DiffReport, format: DiffFormat) -> Result<String> {
+    Ok(match format {
+        DiffFormat::Json => serde_json::to_string_pretty(report)? + "\n",
+        DiffFormat::Human => render_human(report),
+        DiffFormat::Markdown => render_markdown(report),
+    })
+}
+
+fn index_by_relative_path(results: &AnalysisResults) -> BTreeMap<String, &FileInfo> {
+    let root = Path::new(&results.source_path);
+    results
+        .files
+        .iter()
+        .map(|f| {
+            let path = Path::new(&f.path);
+            let relative = path.strip_prefix(root)
+                .ok()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(path);
+            (relative.display().to_string(), f)
+        })
+        .collect()
+}
+
+fn type_of(file: &FileInfo) -> (&str, &str) {
+    (&file.file_type, &file.content_type.mime_type)
+}
+
+/// First algorithm (in `HashAlgorithm` order) with digests in both reports
+fn shared_algorithm(old: &AnalysisResults, new: &AnalysisResults) -> Option<HashAlgorithm> {
+    let algorithms = |r: &AnalysisResults| -> BTreeSet<HashAlgorithm> {
+        r.files.iter().flat_map(|f| f.hashes.keys().copied()).collect()
+    };
+    algorithms(old).intersection(&algorithms(new)).next().copied()
+}
+
+fn digest(file: &FileInfo, algorithm: Option<HashAlgorithm>) -> Option<&str> {
+    file.hashes.get(&algorithm?).map(String::as_str)
+}
+
+/// Digest used to pair moves; every empty file shares one, so they never pair
+fn move_digest(file: &FileInfo, algorithm: Option<HashAlgorithm>) -> Option<&str> {
+    digest(file, algorithm).filter(|_| file.size > 0)
+}
+
+fn is_modified(old_digest: Option<&str>, new_digest: Option<&str>, old: &FileInfo, new: &FileInfo) -> bool {
+    match (old_digest, new_digest) {
+        (Some(a), Some(b)) => a != b,
+        _ => old.size != new.size || !same_mtime(old.modified.as_deref(), new.modified.as_deref()),
+    }
+}
+
+/// Reports predating RFC 3339 times stored `SystemTime`'s Debug output;
+/// those match an RFC 3339 time to the second
+fn same_mtime(old: Option<&str>, new: Option<&str>) -> bool {
+    match (old, new) {
+        (Some(a), Some(b)) if a != b => match (legacy_unix(a), legacy_unix(b)) {
+            (Some(secs), None) => timestamps::parse_unix(b) == Some(secs),
+            (None, Some(secs)) => timestamps::parse_unix(a) == Some(secs),
+            _ => false,
+        },
+        _ => old == new,
+    }
+}
+
+/// Unix seconds of a `SystemTime { tv_sec: .., tv_nsec: .. }` string
+fn legacy_unix(text: &str) -> Option<i64> {
+    let rest = text.strip_prefix("SystemTime { tv_sec: ")?;
+    rest[..rest.find(',')?].parse().ok()
+}
+
+fn change(path: &str, old: Option<&FileInfo>, new: Option<&FileInfo>) -> Change {
+    Change {
+        path: path.to_string(),
+        from: None,
+        old_size: old.map(|f| f.size),
+        new_size: new.map(|f| f.size),
+        old_type: old.map(|f| f.file_type.clone()),
+        new_type: new.map(|f| f.file_type.clone()),
+    }
+}
+
+fn size_of(size: Option<u64>) -> String {
+    size.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string())
+}
+
+fn render_human(report: &DiffReport) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "💎 Diamond Drill diff");
+    let _ = writeln!(out, "   old: {} ({})", report.old_source, report.old_timestamp);
+    let _ = writeln!(out, "   new: {} ({})", report.new_source, report.new_timestamp);
+    let _ = writeln!(out);
+
+    for c in &report.added {
+        let _ = writeln!(out, "+ {} ({} bytes)", c.path, size_of(c.new_size));
+    }
+    for c in &report.removed {
+        let _ = writeln!(out, "- {} ({} bytes)", c.path, size_of(c.old_size));
+    }
+    for c in &report.modified {
+        let _ = writeln!(out, "~ {} ({} → {} bytes)", c.path, size_of(c.old_size), size_of(c.new_size));
+    }
+    for c in &report.moved {
+        let _ = writeln!(out, "> {} → {}", c.from.as_deref().unwrap_or("?"), c.path);
+    }
+    for c in &report.type_changed {
+        let _ = writeln!(
+            out,
+            "! {} ({} → {})",
+            c.path,
+            c.old_type.as_deref().unwrap_or("?"),
+            c.new_type.as_deref().unwrap_or("?")
+        );
+    }
+
+    let t = &report.totals;
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "Added {} | Removed {} | Modified {} | Moved {} | Type changed {} | Unchanged {} | Size Δ {:+} bytes",
+        t.added, t.removed, t.modified, t.moved, t.type_changed, t.unchanged, t.size_delta
+    );
+    out
+}
+
+fn render_markdown(report: &DiffReport) -> String {
+    let mut out = String::new();
+    let t = &report.totals;
+    let _ = writeln!(out, "# Diamond Drill diff\n");
+    let _ = writeln!(out, "- **Old:** `{}` ({})", report.old_source, report.old_timestamp);
+    let _ = writeln!(out, "- **New:** `{}` ({})\n", report.new_source, report.new_timestamp);
+    let _ = writeln!(out, "| Added | Removed | Modified | Moved | Type changed | Unchanged | Size Δ |");
+    let _ = writeln!(out, "|---|---|---|---|---|---|---|");
+    let _ = writeln!(
+        out,
+        "| {} | {} | {} | {} | {} | {} | {:+} |",
+        t.added, t.removed, t.modified, t.moved, t.type_changed, t.unchanged, t.size_delta
+    );
+
+    let sections: [(&str, &[Change]); 5] = [
+        ("Added", &report.added),
+        ("Removed", &report.removed),
+        ("Modified", &report.modified),
+        ("Moved", &report.moved),
+        ("Type changed", &report.type_changed),
+    ];
+    for (title, changes) in sections {
+        if changes.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "\n## {}\n", title);
+        let _ = writeln!(out, "| Path | From | Old size | New size | Old type | New type |");
+        let _ = writeln!(out, "|---|---|---|---|---|---|");
+        for c in changes {
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | {} | {} | {} | {} |",
+                c.path,
+                c.from.as_deref().map(|f| format!("`{}`", f)).unwrap_or_default(),
+                size_of(c.old_size),
+                size_of(c.new_size),
+                c.old_type.as_deref().unwrap_or("-"),
+                c.new_type.as_deref().unwrap_or("-")
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(source: &str, files: serde_json::Value) -> AnalysisResults {
+        let files = files.as_array().unwrap();
+        let total_size: u64 = files.iter().map(|f| f["size"].as_u64().unwrap()).sum();
+        serde_json::from_value(serde_json::json!({
+            "source_path": source,
+            "total_files": files.len(),
+            "total_size": total_size,
+            "file_types": {},
+            "files": files,
+            "timestamp": "1700000000"
+        }))
+        .unwrap()
+    }
+
+    fn file(path: &str, size: u64, file_type: &str, checksum: &str) -> serde_json::Value {
+        serde_json::json!({
+            "path": path,
+            "size": size,
+            "file_type": file_type,
+            "modified": null,
+            "checksum": checksum,
+            "hashes": { "sha256": checksum }
+        })
+    }
+
+    #[test]
+    fn test_diff_classifies_changes() {
+        let old = report("/week1", serde_json::json!([
+            file("/week1/same.txt", 3, "txt", "aaa"),
+            file("/week1/edit.txt", 3, "txt", "bbb"),
+            file("/week1/gone.txt", 4, "txt", "ccc"),
+            file("/week1/old/name.md", 5, "md", "ddd"),
+            file("/week1/doc.pdf", 6, "pdf", "eee"),
+        ]));
+        let new = report("/mnt/week2", serde_json::json!([
+            file("/mnt/week2/same.txt", 3, "txt", "aaa"),
+            file("/mnt/week2/edit.txt", 7, "txt", "fff"),
+            file("/mnt/week2/new/name.md", 5, "md", "ddd"),
+            file("/mnt/week2/doc.pdf", 6, "txt", "ggg"),
+            file("/mnt/week2/fresh.rs", 8, "rs", "hhh"),
+        ]));
+
+        let diff = diff_reports(&old, &new);
+
+        assert_eq!(diff.added[0].path, "fresh.rs");
+        assert_eq!(diff.removed[0].path, "gone.txt");
+        assert_eq!(diff.modified[0].path, "edit.txt");
+        assert_eq!(diff.moved[0].from.as_deref(), Some("old/name.md"));
+        assert_eq!(diff.moved[0].path, "new/name.md");
+        assert_eq!(diff.type_changed[0].new_type.as_deref(), Some("txt"));
+        assert_eq!(diff.totals.unchanged, 1);
+        assert_eq!(diff.totals.size_delta, 29 - 21);
+    }
+
+    #[test]
+    fn test_diff_does_not_pair_empty_files() {
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let old = report("/a", serde_json::json!([file("/a/old/__init__.py", 0, "py", empty)]));
+        let new = report("/a", serde_json::json!([file("/a/new/.gitkeep", 0, "unknown", empty)]));
+
+        let diff = diff_reports(&old, &new);
+        assert!(diff.moved.is_empty());
+        assert_eq!(diff.removed[0].path, "old/__init__.py");
+        assert_eq!(diff.added[0].path, "new/.gitkeep");
+    }
+
+    #[test]
+    fn test_diff_without_checksums_uses_size() {
+        let old = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 1, "file_type": "unknown", "modified": "t1"},
+            {"path": "/a/y", "size": 1, "file_type": "unknown", "modified": "t1"},
+        ]));
+        let new = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 2, "file_type": "unknown", "modified": "t1"},
+            {"path": "/a/y", "size": 1, "file_type": "unknown", "modified": "t1"},
+        ]));
+
+        let diff = diff_reports(&old, &new);
+        assert_eq!(diff.totals.modified, 1);
+        assert_eq!(diff.totals.unchanged, 1);
+    }
+
+    #[test]
+    fn test_diff_uses_digest_both_reports_have() {
+        let old = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 3, "file_type": "txt", "modified": "t1",
+             "checksum": "s256", "hashes": {"sha256": "s256"}},
+        ]));
+        let new = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 3, "file_type": "txt", "modified": "t2",
+             "checksum": "s512", "hashes": {"sha512": "s512", "sha256": "s256"}},
+        ]));
+        assert_eq!(diff_reports(&old, &new).totals.unchanged, 1);
+
+        // No algorithm in common: size and mtime decide
+        let new = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 3, "file_type": "txt", "modified": "t1",
+             "checksum": "s512", "hashes": {"sha512": "s512"}},
+        ]));
+        assert_eq!(diff_reports(&old, &new).totals.unchanged, 1);
+    }
+
+    #[test]
+    fn test_diff_matches_legacy_mtime_format() {
+        let old = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 1, "file_type": "unknown",
+             "modified": "SystemTime { tv_sec: 1700000000, tv_nsec: 250000000 }"},
+            {"path": "/a/y", "size": 1, "file_type": "unknown",
+             "modified": "SystemTime { tv_sec: 1700000000, tv_nsec: 0 }"},
+        ]));
+        let new = report("/a", serde_json::json!([
+            {"path": "/a/x", "size": 1, "file_type": "unknown",
+             "modified": "2023-11-14T22:13:20.250000000Z"},
+            {"path": "/a/y", "size": 1, "file_type": "unknown",
+             "modified": "2023-11-14T22:13:21.000000000Z"},
+        ]));
+
+        let diff = diff_reports(&old, &new);
+        assert_eq!(diff.totals.unchanged, 1);
+        assert_eq!(diff.modified[0].path, "y");
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let old = report("/a", serde_json::json!([file("/a/x.txt", 1, "txt", "1")]));
+        let new = report("/a", serde_json::json!([file("/a/y.txt", 2, "txt", "2")]));
+        let diff = diff_reports(&old, &new);
+
+        let human = render(&diff, DiffFormat::Human).unwrap();
+        assert!(human.contains("+ y.txt"));
+        assert!(human.contains("- x.txt"));
+
+        let markdown = render(&diff, DiffFormat::Markdown).unwrap();
+        assert!(markdown.contains("## Added"));
+        assert!(markdown.contains("| `x.txt` |"));
+
+        let json: DiffReport = serde_json::from_str(&render(&diff, DiffFormat::Json).unwrap()).unwrap();
+        assert_eq!(json, diff);
+    }
+}
@@ -0,0 +1,321 @@
+//! Disk Usage Module
+//!
+//! Size totals that count every inode once, so hard links are not
+//! double-counted, in two flavors: apparent (file lengths) and on disk
+//! (allocated blocks, which is what a copy of a sparse image costs when holes
+//! are preserved). Hard-link groups are listed by device and inode. A
+//! `du`-style roll-up adds recursive totals per directory, the largest files
+//! and directories, and a size histogram.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use diamond_drill::engine::FileInfo;
+
+/// Paths in the tree sharing one inode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardLinkGroup {
+    pub dev: u64,
+    pub inode: u64,
+    /// Link count of the inode; more than `paths.len()` when some links lie
+    /// outside the source
+    pub links: u64,
+    pub size: u64,
+    pub paths: Vec<String>,
+}
+
+/// Recursive totals of one directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectoryUsage {
+    pub path: String,
+    /// Levels below the source root, which is 0
+    pub depth: usize,
+    /// Files below this directory, at any depth
+    pub files: usize,
+    /// Apparent bytes below this directory
+    pub size: u64,
+    /// Allocated bytes below this directory
+    pub disk_size: u64,
+    /// Latest mtime of any file below this directory
+    #[serde(default)]
+    pub newest_modified: Option<String>,
+}
+
+/// A file or directory ranked by size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+/// Files whose size falls in `[min, max)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeBucket {
+    pub min: u64,
+    pub max: u64,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// `du`-style view of the analyzed tree
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Every directory holding analyzed files, sorted by path so parents
+    /// precede their children
+    #[serde(default)]
+    pub directories: Vec<DirectoryUsage>,
+    /// Largest files, biggest first
+    #[serde(default)]
+    pub top_files: Vec<SizeEntry>,
+    /// Largest directories below the source root, biggest first
+    #[serde(default)]
+    pub top_directories: Vec<SizeEntry>,
+    /// File counts in buckets growing fourfold from 1 KiB; empty buckets
+    /// between the smallest and largest are kept
+    #[serde(default)]
+    pub size_histogram: Vec<SizeBucket>,
+}
+
+/// Running totals over analyzed files
+#[derive(Debug)]
+pub struct UsageTotals {
+    root: PathBuf,
+    top_n: usize,
+    /// Inodes with more than one link already counted
+    seen: HashSet<(u64, u64)>,
+    /// Sum of file lengths, each inode once
+    pub apparent: u64,
+    /// Sum of allocated bytes, each inode once
+    pub on_disk: u64,
+    directories: BTreeMap<PathBuf, DirectoryUsage>,
+    /// The `top_n` largest files so far, smallest on top
+    largest: BinaryHeap<Reverse<(u64, String)>>,
+    buckets: BTreeMap<usize, SizeBucket>,
+}
+
+impl UsageTotals {
+    /// Totals for files under `root`, keeping the `top_n` largest entries
+    pub fn new(root: &str, top_n: usize) -> Self {
+        Self {
+            root: PathBuf::from(root),
+            top_n,
+            seen: HashSet::new(),
+            apparent: 0,
+            on_disk: 0,
+            directories: BTreeMap::new(),
+            largest: BinaryHeap::new(),
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Count a file unless it is an archive entry or another link to a
+    /// counted inode
+    pub fn add(&mut self, info: &FileInfo) {
+        if info.archive_entry.is_some() {
+            return;
+        }
+        let allocated = match &info.storage {
+            Some(s) if s.links > 1 && !self.seen.insert((s.dev, s.inode)) => return,
+            Some(s) => s.allocated,
+            // No block counts on this platform: assume dense
+            None => info.size,
+        };
+        self.apparent += info.size;
+        self.on_disk += allocated;
+
+        let path = Path::new(&info.path);
+        for dir in path.ancestors().skip(1).take_while(|d| d.starts_with(&self.root)) {
+            let usage = self.directories.entry(dir.to_path_buf()).or_insert_with(|| DirectoryUsage {
+                path: dir.display().to_string(),
+                depth: dir.components().count() - self.root.components().count(),
+                files: 0,
+                size: 0,
+                disk_size: 0,
+                newest_modified: None,
+            });
+            usage.files += 1;
+            usage.size += info.size;
+            usage.disk_size += allocated;
+            // RFC 3339 UTC timestamps of equal width order as text
+            if info.modified > usage.newest_modified {
+                usage.newest_modified.clone_from(&info.modified);
+            }
+        }
+
+        self.largest.push(Reverse((info.size, info.path.clone())));
+        if self.largest.len() > self.top_n {
+            self.largest.pop();
+        }
+
+        let index = bucket_index(info.size);
+        let (min, max) = bucket_bounds(index);
+        let bucket = self.buckets.entry(index).or_insert(SizeBucket {
+            min,
+            max,
+            files: 0,
+            bytes: 0,
+        });
+        bucket.files += 1;
+        bucket.bytes += info.size;
+    }
+
+    /// Roll-up, rankings and histogram of everything added
+    pub fn report(&self) -> UsageReport {
+        let mut top_files: Vec<SizeEntry> = self.largest.iter()
+            .map(|Reverse((size, path))| SizeEntry { path: path.clone(), size: *size })
+            .collect();
+        top_files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+
+        let mut top_directories: Vec<SizeEntry> = self.directories.values()
+            .filter(|d| d.depth > 0)
+            .map(|d| SizeEntry { path: d.path.clone(), size: d.size })
+            .collect();
+        top_directories.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        top_directories.truncate(self.top_n);
+
+        let size_histogram = match (self.buckets.keys().next(), self.buckets.keys().last()) {
+            (Some(&first), Some(&last)) => (first..=last)
+                .map(|i| self.buckets.get(&i).cloned().unwrap_or_else(|| {
+                    let (min, max) = bucket_bounds(i);
+                    SizeBucket { min, max, files: 0, bytes: 0 }
+                }))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        UsageReport {
+            directories: self.directories.values().cloned().collect(),
+            top_files,
+            top_directories,
+            size_histogram,
+        }
+    }
+}
+
+/// Histogram bucket of a size: 0 holds empty files, 1 holds up to 1 KiB,
+/// then each bucket spans four times the previous one
+fn bucket_index(size: u64) -> usize {
+    match size {
+        0 => 0,
+        1..=1023 => 1,
+        _ => 2 + (63 - size.leading_zeros() as usize - 10) / 2,
+    }
+}
+
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    match index {
+        0 => (0, 1),
+        1 => (1, 1024),
+        i => {
+            let shift = 10 + 2 * (i - 2) as u32;
+            (1u64 << shift, 1u64.checked_shl(shift + 2).unwrap_or(u64::MAX))
+        }
+    }
+}
+
+/// Inodes with more than one link, in order of first appearance
+pub fn hard_link_groups(files: &[FileInfo]) -> Vec<HardLinkGroup> {
+    let mut groups: Vec<HardLinkGroup> = Vec::new();
+    let mut by_inode = HashMap::new();
+    for file in files {
+        let Some(s) = file.storage.as_ref().filter(|s| s.links > 1) else {
+            continue;
+        };
+        let index = *by_inode.entry((s.dev, s.inode)).or_insert_with(|| {
+            groups.push(HardLinkGroup {
+                dev: s.dev,
+                inode: s.inode,
+                links: s.links,
+                size: file.size,
+                paths: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[index].paths.push(file.path.clone());
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer;
+    use crate::config::Config;
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_hard_links_counted_once() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.bin"), vec![1u8; 10_000]).unwrap();
+        fs::hard_link(root.join("a.bin"), root.join("b.bin")).unwrap();
+        fs::write(root.join("c.txt"), "hello").unwrap();
+        File::create(root.join("disk.img")).unwrap().set_len(8 * 1024 * 1024).unwrap();
+
+        let results = analyzer::analyze(root.to_str().unwrap(), &Config::default()).await.unwrap();
+        assert_eq!(results.total_files, 4);
+        assert_eq!(results.total_size, 10_000 + 5 + 8 * 1024 * 1024);
+        assert!(results.disk_size < 1024 * 1024);
+
+        assert_eq!(results.hard_links.len(), 1);
+        let group = &results.hard_links[0];
+        assert_eq!(group.links, 2);
+        assert_eq!(group.size, 10_000);
+        assert!(group.paths[0].ends_with("a.bin") && group.paths[1].ends_with("b.bin"));
+
+        let image = results.files.iter().find(|f| f.path.ends_with("disk.img")).unwrap();
+        assert!(image.storage.as_ref().unwrap().sparse);
+    }
+
+    #[tokio::test]
+    async fn test_directory_rollup() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src/deep")).unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("a.txt"), "").unwrap();
+        fs::write(root.join("docs/guide.md"), vec![b'x'; 3000]).unwrap();
+        fs::write(root.join("src/main.rs"), vec![b'x'; 500]).unwrap();
+        fs::write(root.join("src/deep/blob.bin"), vec![7u8; 70_000]).unwrap();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options().write(true).open(root.join("src/deep/blob.bin")).unwrap().set_modified(old).unwrap();
+
+        let mut config = Config::default();
+        config.analysis.top_n = 2;
+        let source = root.to_str().unwrap();
+        let usage = analyzer::analyze(source, &config).await.unwrap().usage;
+        let relative = |path: &str| path.strip_prefix(source).unwrap().to_string();
+
+        let dirs: Vec<_> = usage.directories.iter()
+            .map(|d| (relative(&d.path), d.depth, d.files, d.size))
+            .collect();
+        assert_eq!(dirs, [
+            ("".to_string(), 0, 4, 73_500),
+            ("/docs".to_string(), 1, 1, 3000),
+            ("/src".to_string(), 1, 2, 70_500),
+            ("/src/deep".to_string(), 2, 1, 70_000),
+        ]);
+        assert_eq!(usage.directories[3].newest_modified.as_deref(), Some("2001-09-09T01:46:40.000000000Z"));
+        assert!(usage.directories[2].newest_modified > usage.directories[3].newest_modified);
+
+        let top: Vec<_> = usage.top_files.iter().map(|f| (relative(&f.path), f.size)).collect();
+        assert_eq!(top, [("/src/deep/blob.bin".to_string(), 70_000), ("/docs/guide.md".to_string(), 3000)]);
+        let top: Vec<_> = usage.top_directories.iter().map(|d| relative(&d.path)).collect();
+        assert_eq!(top, ["/src", "/src/deep"]);
+
+        // Empty, under 1 KiB, then 1-4, 4-16, 16-64 and 64-256 KiB
+        let histogram: Vec<_> = usage.size_histogram.iter().map(|b| (b.min, b.max, b.files)).collect();
+        assert_eq!(histogram, [
+            (0, 1, 1),
+            (1, 1024, 1),
+            (1024, 4096, 1),
+            (4096, 16_384, 0),
+            (16_384, 65_536, 0),
+            (65_536, 262_144, 1),
+        ]);
+    }
+}
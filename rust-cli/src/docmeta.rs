@@ -0,0 +1,1071 @@
+//! Document Metadata Module
+//!
+//! Provenance embedded in images and documents: EXIF (camera, GPS position,
+//! timestamps) from JPEG and TIFF, dimensions and text chunks from PNG, the
+//! Info dictionary and XMP packet of PDFs, and `docProps` of Office Open XML
+//! files. Parsing works on an in-memory image; malformed input yields whatever
+//! could be read. Dates are normalized to ISO 8601 where the source format
+//! allows it.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use flate2::read::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+
+/// PNG text chunks kept per file
+const MAX_TEXT_ENTRIES: usize = 64;
+
+/// Longest text value kept, in characters
+const MAX_TEXT_LEN: usize = 1024;
+
+/// Largest compressed text chunk or `docProps` part read
+const MAX_PART_LEN: u64 = 1024 * 1024;
+
+/// IFD entries read per directory
+const MAX_IFD_ENTRIES: usize = 512;
+
+/// PDF page-tree nodes inspected for `/Count`
+const MAX_PAGE_NODES: usize = 10_000;
+
+/// PDF object streams decompressed when looking for the page tree
+const MAX_OBJECT_STREAMS: usize = 64;
+
+/// Largest decompressed PDF object stream
+const MAX_OBJECT_STREAM_LEN: u64 = 4 * 1024 * 1024;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_XMP: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PNG_XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+// EXIF tags
+const TAG_WIDTH: u16 = 0x0100;
+const TAG_HEIGHT: u16 = 0x0101;
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_SOFTWARE: u16 = 0x0131;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_PIXEL_WIDTH: u16 = 0xA002;
+const TAG_PIXEL_HEIGHT: u16 = 0xA003;
+const GPS_LATITUDE_REF: u16 = 1;
+const GPS_LATITUDE: u16 = 2;
+const GPS_LONGITUDE_REF: u16 = 3;
+const GPS_LONGITUDE: u16 = 4;
+const GPS_ALTITUDE_REF: u16 = 5;
+const GPS_ALTITUDE: u16 = 6;
+
+// EXIF field types
+const TYPE_BYTE: u16 = 1;
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataFormat {
+    Jpeg,
+    Tiff,
+    Png,
+    Pdf,
+    /// docx, xlsx and pptx
+    Ooxml,
+}
+
+/// Where a photo was taken, in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Meters above sea level
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+/// Metadata embedded in an image or document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    pub format: MetadataFormat,
+    /// Image dimensions in pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// PDF pages, Word pages or PowerPoint slides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// EXIF Artist, PDF Author or Office creator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_make: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera_model: Option<String>,
+    /// Authoring application: EXIF Software, PDF Creator or Office Application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub software: Option<String>,
+    /// PDF Producer (the library that wrote the file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gps: Option<GpsPosition>,
+    /// PNG `tEXt`, `zTXt` and `iTXt` chunks by keyword
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub text: BTreeMap<String, String>,
+}
+
+impl DocumentMetadata {
+    fn new(format: MetadataFormat) -> Self {
+        Self {
+            format,
+            width: None,
+            height: None,
+            pages: None,
+            title: None,
+            author: None,
+            last_modified_by: None,
+            revision: None,
+            camera_make: None,
+            camera_model: None,
+            software: None,
+            producer: None,
+            created: None,
+            modified: None,
+            gps: None,
+            text: BTreeMap::new(),
+        }
+    }
+}
+
+/// Format whose metadata can be extracted, from the sniffed kind and header
+pub fn format_of(head: &[u8], kind: Option<&str>, ext: Option<&str>) -> Option<MetadataFormat> {
+    match kind {
+        Some("jpg") => Some(MetadataFormat::Jpeg),
+        Some("png") => Some(MetadataFormat::Png),
+        Some("pdf") => Some(MetadataFormat::Pdf),
+        Some("docx" | "xlsx" | "pptx") => Some(MetadataFormat::Ooxml),
+        Some("zip") if ext.is_some_and(is_ooxml_extension) => Some(MetadataFormat::Ooxml),
+        _ if head.starts_with(b"II*\0") || head.starts_with(b"MM\0*") => Some(MetadataFormat::Tiff),
+        _ => None,
+    }
+}
+
+fn is_ooxml_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "docx" | "docm" | "dotx" | "dotm" | "xlsx" | "xlsm" | "xltx" | "xltm" | "pptx" | "pptm" | "potx" | "potm" | "ppsx"
+    )
+}
+
+/// Extract metadata from a whole file; `None` when nothing was found
+pub fn extract(data: &[u8], format: MetadataFormat) -> Option<DocumentMetadata> {
+    let mut meta = DocumentMetadata::new(format);
+    match format {
+        MetadataFormat::Jpeg => parse_jpeg(data, &mut meta),
+        MetadataFormat::Tiff => parse_exif(data, &mut meta),
+        MetadataFormat::Png => parse_png(data, &mut meta),
+        MetadataFormat::Pdf => parse_pdf(data, &mut meta),
+        MetadataFormat::Ooxml => parse_ooxml(data, &mut meta),
+    }
+    (meta != DocumentMetadata::new(format)).then_some(meta)
+}
+
+fn parse_jpeg(data: &[u8], meta: &mut DocumentMetadata) {
+    let mut pos = 2;
+    while let (Some(&0xFF), Some(&marker)) = (data.get(pos), data.get(pos + 1)) {
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD8 => {
+                pos += 2;
+                continue;
+            }
+            // Image data or end of image: no more headers
+            0xD9 | 0xDA => break,
+            _ => {}
+        }
+        let Some(len) = data.get(pos + 2..pos + 4).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize) else {
+            break;
+        };
+        let Some(segment) = len.checked_sub(2).and_then(|n| data.get(pos + 4..pos + 4 + n)) else {
+            break;
+        };
+        match marker {
+            0xE1 if segment.starts_with(b"Exif\0\0") => parse_exif(&segment[6..], meta),
+            0xE1 if segment.starts_with(JPEG_XMP) => parse_xmp(&segment[JPEG_XMP.len()..], meta),
+            // Start of frame (not DHT, JPG or DAC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) && segment.len() >= 5 => {
+                meta.height = Some(u16::from_be_bytes([segment[1], segment[2]]) as u32);
+                meta.width = Some(u16::from_be_bytes([segment[3], segment[4]]) as u32);
+            }
+            _ => {}
+        }
+        pos += 2 + len;
+    }
+}
+
+/// Bounds-checked reads in the TIFF header's byte order
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+/// One IFD entry; `value` is the offset of the value, inline or not
+struct IfdEntry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: usize,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let b = self.data.get(offset..offset.checked_add(2)?)?;
+        let b = [b[0], b[1]];
+        Some(if self.big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let b = self.data.get(offset..offset.checked_add(4)?)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if self.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+
+    fn entries(&self, ifd: usize) -> Vec<IfdEntry> {
+        let count = self.u16(ifd).unwrap_or(0) as usize;
+        (0..count.min(MAX_IFD_ENTRIES))
+            .map_while(|i| {
+                let at = ifd + 2 + i * 12;
+                let kind = self.u16(at + 2)?;
+                let count = self.u32(at + 4)?;
+                let size = type_size(kind).saturating_mul(count as usize);
+                let value = if size <= 4 { at + 8 } else { self.u32(at + 8)? as usize };
+                Some(IfdEntry { tag: self.u16(at)?, kind, count, value })
+            })
+            .collect()
+    }
+
+    fn ascii(&self, entry: &IfdEntry) -> Option<String> {
+        if entry.kind != TYPE_ASCII {
+            return None;
+        }
+        let bytes = self.data.get(entry.value..entry.value.checked_add(entry.count as usize)?)?;
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        clean(&String::from_utf8_lossy(&bytes[..end]))
+    }
+
+    fn uint(&self, entry: &IfdEntry) -> Option<u32> {
+        match entry.kind {
+            TYPE_BYTE => self.data.get(entry.value).map(|&b| b as u32),
+            TYPE_SHORT => self.u16(entry.value).map(u32::from),
+            TYPE_LONG => self.u32(entry.value),
+            _ => None,
+        }
+    }
+
+    /// The `index`th unsigned rational of an entry
+    fn rational(&self, entry: &IfdEntry, index: usize) -> Option<f64> {
+        if entry.kind != TYPE_RATIONAL || index >= entry.count as usize {
+            return None;
+        }
+        let at = entry.value.checked_add(index * 8)?;
+        let (num, den) = (self.u32(at)?, self.u32(at + 4)?);
+        (den != 0).then(|| num as f64 / den as f64)
+    }
+
+    /// Degrees, minutes and seconds as decimal degrees
+    fn degrees(&self, entry: &IfdEntry) -> Option<f64> {
+        let d = self.rational(entry, 0)?;
+        let m = self.rational(entry, 1).unwrap_or(0.0);
+        let s = self.rational(entry, 2).unwrap_or(0.0);
+        Some(d + m / 60.0 + s / 3600.0)
+    }
+}
+
+fn type_size(kind: u16) -> usize {
+    match kind {
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+/// Parse a TIFF structure (a TIFF file or a JPEG/PNG EXIF block)
+fn parse_exif(data: &[u8], meta: &mut DocumentMetadata) {
+    let big_endian = match data.get(..4) {
+        Some(b"II*\0") => false,
+        Some(b"MM\0*") => true,
+        _ => return,
+    };
+    let tiff = Tiff { data, big_endian };
+    let Some(ifd0) = tiff.u32(4) else { return };
+
+    let (mut exif_ifd, mut gps_ifd) = (None, None);
+    for entry in tiff.entries(ifd0 as usize) {
+        match entry.tag {
+            TAG_WIDTH => meta.width = tiff.uint(&entry),
+            TAG_HEIGHT => meta.height = tiff.uint(&entry),
+            TAG_MAKE => meta.camera_make = tiff.ascii(&entry),
+            TAG_MODEL => meta.camera_model = tiff.ascii(&entry),
+            TAG_SOFTWARE => meta.software = tiff.ascii(&entry),
+            TAG_DATE_TIME => meta.modified = tiff.ascii(&entry).and_then(|d| exif_date(&d)),
+            TAG_ARTIST => meta.author = tiff.ascii(&entry),
+            TAG_EXIF_IFD => exif_ifd = tiff.uint(&entry),
+            TAG_GPS_IFD => gps_ifd = tiff.uint(&entry),
+            _ => {}
+        }
+    }
+
+    for entry in exif_ifd.map(|ifd| tiff.entries(ifd as usize)).unwrap_or_default() {
+        match entry.tag {
+            TAG_DATE_TIME_ORIGINAL => meta.created = tiff.ascii(&entry).and_then(|d| exif_date(&d)),
+            TAG_PIXEL_WIDTH if meta.width.is_none() => meta.width = tiff.uint(&entry),
+            TAG_PIXEL_HEIGHT if meta.height.is_none() => meta.height = tiff.uint(&entry),
+            _ => {}
+        }
+    }
+
+    if let Some(ifd) = gps_ifd {
+        meta.gps = parse_gps(&tiff, &tiff.entries(ifd as usize));
+    }
+}
+
+fn parse_gps(tiff: &Tiff, entries: &[IfdEntry]) -> Option<GpsPosition> {
+    let find = |tag| entries.iter().find(|e| e.tag == tag);
+    let hemisphere = |tag, negative| find(tag).and_then(|e| tiff.ascii(e)).is_some_and(|r| r == negative);
+
+    let mut latitude = tiff.degrees(find(GPS_LATITUDE)?)?;
+    let mut longitude = tiff.degrees(find(GPS_LONGITUDE)?)?;
+    if hemisphere(GPS_LATITUDE_REF, "S") {
+        latitude = -latitude;
+    }
+    if hemisphere(GPS_LONGITUDE_REF, "W") {
+        longitude = -longitude;
+    }
+    let below_sea = find(GPS_ALTITUDE_REF).and_then(|e| tiff.uint(e)) == Some(1);
+    let altitude = find(GPS_ALTITUDE)
+        .and_then(|e| tiff.rational(e, 0))
+        .map(|a| round(if below_sea { -a } else { a }, 2));
+
+    Some(GpsPosition {
+        latitude: round(latitude, 6),
+        longitude: round(longitude, 6),
+        altitude,
+    })
+}
+
+/// `YYYY:MM:DD HH:MM:SS` as ISO 8601; `None` for the all-zero placeholder
+fn exif_date(value: &str) -> Option<String> {
+    let b = value.as_bytes();
+    let is_exif = b.len() >= 19
+        && b.iter().take(19).enumerate().all(|(i, c)| match i {
+            4 | 7 | 13 | 16 => *c == b':',
+            10 => *c == b' ',
+            _ => c.is_ascii_digit(),
+        });
+    if !is_exif {
+        return Some(value.to_string());
+    }
+    if value.starts_with("0000") {
+        return None;
+    }
+    Some(format!("{}-{}-{}T{}", &value[..4], &value[5..7], &value[8..10], &value[11..19]))
+}
+
+fn parse_png(data: &[u8], meta: &mut DocumentMetadata) {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return;
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    while let Some(header) = data.get(pos..pos + 8) {
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let Some(body) = data.get(pos + 8..(pos + 8).saturating_add(len)) else {
+            break;
+        };
+        match &header[4..] {
+            b"IHDR" if body.len() >= 8 => {
+                meta.width = Some(u32::from_be_bytes([body[0], body[1], body[2], body[3]]));
+                meta.height = Some(u32::from_be_bytes([body[4], body[5], body[6], body[7]]));
+            }
+            b"tEXt" | b"zTXt" | b"iTXt" => {
+                if let Some((keyword, text)) = png_text(&header[4..], body) {
+                    add_png_text(meta, keyword, text);
+                }
+            }
+            b"eXIf" => parse_exif(body, meta),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+}
+
+/// Keyword and text of a PNG text chunk
+fn png_text(kind: &[u8], body: &[u8]) -> Option<(String, String)> {
+    let nul = body.iter().position(|&b| b == 0)?;
+    let keyword = latin1(&body[..nul]);
+    let rest = &body[nul + 1..];
+    let text = match kind {
+        b"tEXt" => latin1(rest),
+        b"zTXt" => latin1(&inflate(rest.get(1..)?, MAX_PART_LEN)?),
+        _ => {
+            let (&compressed, rest) = rest.split_first()?;
+            // Compression method, then NUL-terminated language and translated keyword
+            let rest = rest.get(1..)?;
+            let lang = rest.iter().position(|&b| b == 0)?;
+            let rest = &rest[lang + 1..];
+            let translated = rest.iter().position(|&b| b == 0)?;
+            let text = &rest[translated + 1..];
+            if compressed == 1 {
+                String::from_utf8_lossy(&inflate(text, MAX_PART_LEN)?).into_owned()
+            } else {
+                String::from_utf8_lossy(text).into_owned()
+            }
+        }
+    };
+    Some((keyword, text))
+}
+
+fn add_png_text(meta: &mut DocumentMetadata, keyword: String, text: String) {
+    if keyword == PNG_XMP_KEYWORD {
+        parse_xmp(text.as_bytes(), meta);
+        return;
+    }
+    let Some(value) = clean(&text) else { return };
+    match keyword.as_str() {
+        "Title" => meta.title = Some(value.clone()),
+        "Author" => meta.author = Some(value.clone()),
+        "Software" => meta.software = Some(value.clone()),
+        "Creation Time" => meta.created = Some(value.clone()),
+        _ => {}
+    }
+    if meta.text.len() < MAX_TEXT_ENTRIES {
+        meta.text.insert(keyword, value);
+    }
+}
+
+fn inflate(data: &[u8], limit: u64) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(data).take(limit).read_to_end(&mut out).ok()?;
+    Some(out)
+}
+
+fn parse_pdf(data: &[u8], meta: &mut DocumentMetadata) {
+    if let Some(info) = pdf_info(data) {
+        meta.title = pdf_string(info, b"/Title");
+        meta.author = pdf_string(info, b"/Author");
+        meta.software = pdf_string(info, b"/Creator");
+        meta.producer = pdf_string(info, b"/Producer");
+        meta.created = pdf_string(info, b"/CreationDate").map(|d| pdf_date(&d));
+        meta.modified = pdf_string(info, b"/ModDate").map(|d| pdf_date(&d));
+    }
+    // PDF 1.5+ usually keeps the page tree in compressed object streams
+    meta.pages = pdf_page_count(data)
+        .or_else(|| pdf_object_streams(data).filter_map(|s| pdf_page_count(&s)).max());
+    // The XMP packet fills fields the Info dictionary lacks (or keeps compressed)
+    if let Some(start) = find(data, b"<x:xmpmeta") {
+        let packet = &data[start..];
+        let end = find(packet, b"</x:xmpmeta>").map_or(packet.len(), |e| e + 12);
+        parse_xmp(&packet[..end.min(MAX_PART_LEN as usize)], meta);
+    }
+}
+
+/// The trailer's `/Info` dictionary, following its indirect reference
+fn pdf_info(data: &[u8]) -> Option<&[u8]> {
+    let at = rfind(data, b"/Info")? + 5;
+    let rest = skip_ws(&data[at..]);
+    if rest.starts_with(b"<<") {
+        return pdf_dict(rest);
+    }
+    let (num, rest) = pdf_uint(rest)?;
+    let (gen, rest) = pdf_uint(skip_ws(rest))?;
+    if !skip_ws(rest).starts_with(b"R") {
+        return None;
+    }
+
+    // The last definition wins after incremental updates
+    let header = format!("{} {} obj", num, gen);
+    let mut end = data.len();
+    let start = loop {
+        let i = rfind(&data[..end], header.as_bytes())?;
+        if i == 0 || !data[i - 1].is_ascii_digit() {
+            break i + header.len();
+        }
+        end = i;
+    };
+    let body = skip_ws(&data[start..]);
+    body.starts_with(b"<<").then(|| pdf_dict(body)).flatten()
+}
+
+/// The `<< ... >>` dictionary at the start of `data`, nesting and strings included
+fn pdf_dict(data: &[u8]) -> Option<&[u8]> {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i + 1 < data.len() {
+        match &data[i..i + 2] {
+            b"<<" => {
+                depth += 1;
+                i += 2;
+            }
+            b">>" => {
+                depth = depth.checked_sub(1)?;
+                i += 2;
+                if depth == 0 {
+                    return Some(&data[..i]);
+                }
+            }
+            [b'(', _] => i += 1 + pdf_literal(&data[i + 1..])?.1,
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Text value of `key` in a dictionary, from a literal or hex string
+fn pdf_string(dict: &[u8], key: &[u8]) -> Option<String> {
+    let mut from = 0;
+    let value = loop {
+        let at = from + find(&dict[from..], key)? + key.len();
+        // Skip longer keys sharing the prefix (`/Title` vs `/Titles`)
+        if dict.get(at).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            from = at;
+            continue;
+        }
+        break skip_ws(&dict[at..]);
+    };
+    let bytes = match value.first()? {
+        b'(' => pdf_literal(&value[1..])?.0,
+        b'<' if value.get(1) != Some(&b'<') => pdf_hex(&value[1..])?,
+        _ => return None,
+    };
+    let text = if let Some(utf16) = bytes.strip_prefix(b"\xFE\xFF") {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+        String::from_utf16_lossy(&units)
+    } else if let Some(utf8) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        // PDFDocEncoding matches Latin-1 for printable text
+        latin1(&bytes)
+    };
+    clean(&text)
+}
+
+/// Unescaped bytes of a literal string whose `(` was already consumed, and
+/// the length consumed including the closing `)`
+fn pdf_literal(data: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut out = Vec::new();
+    let mut depth = 1;
+    let mut i = 0;
+    while let Some(&b) = data.get(i) {
+        i += 1;
+        match b {
+            b'\\' => {
+                let &next = data.get(i)?;
+                i += 1;
+                match next {
+                    b'n' => out.push(b'\n'),
+                    b'r' => out.push(b'\r'),
+                    b't' => out.push(b'\t'),
+                    b'b' => out.push(0x08),
+                    b'f' => out.push(0x0C),
+                    b'0'..=b'7' => {
+                        let mut value = (next - b'0') as u32;
+                        for _ in 0..2 {
+                            match data.get(i) {
+                                Some(&d @ b'0'..=b'7') => {
+                                    value = value * 8 + (d - b'0') as u32;
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        out.push(value as u8);
+                    }
+                    // Line continuation
+                    b'\r' | b'\n' => {
+                        if next == b'\r' && data.get(i) == Some(&b'\n') {
+                            i += 1;
+                        }
+                    }
+                    other => out.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                out.push(b);
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((out, i));
+                }
+                out.push(b);
+            }
+            _ => out.push(b),
+        }
+    }
+    None
+}
+
+/// Bytes of a hex string whose `<` was already consumed
+fn pdf_hex(data: &[u8]) -> Option<Vec<u8>> {
+    let end = data.iter().position(|&b| b == b'>')?;
+    let mut digits: Vec<u8> = data[..end]
+        .iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if digits.len() % 2 == 1 {
+        digits.push(0);
+    }
+    Some(digits.chunks(2).map(|p| p[0] << 4 | p[1]).collect())
+}
+
+/// `D:YYYYMMDDHHmmSSOHH'mm'` as ISO 8601; anything else is kept verbatim
+fn pdf_date(value: &str) -> String {
+    let raw = value.strip_prefix("D:").unwrap_or(value);
+    let digits = raw.bytes().take_while(u8::is_ascii_digit).count();
+    if digits < 4 || digits % 2 == 1 {
+        return value.to_string();
+    }
+    let part = |i: usize, default: &'static str| raw.get(i..i + 2).filter(|_| i + 2 <= digits).unwrap_or(default);
+    let mut date = format!(
+        "{}-{}-{}T{}:{}:{}",
+        &raw[..4], part(4, "01"), part(6, "01"), part(8, "00"), part(10, "00"), part(12, "00")
+    );
+    let zone = &raw[digits..];
+    match zone.as_bytes().first() {
+        Some(b'Z') => date.push('Z'),
+        // Decoded strings may hold multi-byte characters: only slice after ASCII hours
+        Some(&sign @ (b'+' | b'-')) => {
+            if let Some(hours) = zone.get(1..3).filter(|h| h.bytes().all(|b| b.is_ascii_digit())) {
+                let minutes: String = zone[3..].chars().filter(char::is_ascii_digit).take(2).collect();
+                let minutes = if minutes.len() == 2 { minutes } else { "00".to_string() };
+                date.push_str(&format!("{}{}:{}", sign as char, hours, minutes));
+            }
+        }
+        _ => {}
+    }
+    date
+}
+
+/// Largest `/Count` of a `/Type /Pages` node, which is the page-tree root
+fn pdf_page_count(data: &[u8]) -> Option<u32> {
+    let mut count = None;
+    let mut from = 0;
+    for _ in 0..MAX_PAGE_NODES {
+        let Some(i) = find(&data[from..], b"/Type") else { break };
+        let at = from + i;
+        from = at + 5;
+        let rest = skip_ws(&data[from..]);
+        if !rest.starts_with(b"/Pages") || rest.get(6).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            continue;
+        }
+        let Some(open) = rfind(&data[at.saturating_sub(4096)..at], b"<<") else { continue };
+        let Some(dict) = pdf_dict(&data[at.saturating_sub(4096) + open..]) else { continue };
+        let Some(c) = find(dict, b"/Count").and_then(|c| pdf_uint(skip_ws(&dict[c + 6..]))) else {
+            continue;
+        };
+        count = count.max(u32::try_from(c.0).ok());
+    }
+    count
+}
+
+/// Decompressed `/ObjStm` streams that use `FlateDecode`
+fn pdf_object_streams(data: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let mut from = 0;
+    std::iter::from_fn(move || loop {
+        from += find(&data[from..], b"/ObjStm")? + 7;
+        let start = from + find(&data[from..], b"stream")? + 6;
+        let body = &data[start..];
+        let body = body.strip_prefix(b"\r\n").or_else(|| body.strip_prefix(b"\n")).unwrap_or(body);
+        let end = find(body, b"endstream").unwrap_or(body.len());
+        if let Some(objects) = inflate(&body[..end], MAX_OBJECT_STREAM_LEN) {
+            return Some(objects);
+        }
+    })
+    .take(MAX_OBJECT_STREAMS)
+}
+
+fn pdf_uint(data: &[u8]) -> Option<(u64, &[u8])> {
+    let len = data.iter().take_while(|b| b.is_ascii_digit()).count();
+    let value = std::str::from_utf8(&data[..len]).ok()?.parse().ok()?;
+    Some((value, &data[len..]))
+}
+
+fn skip_ws(data: &[u8]) -> &[u8] {
+    let n = data.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    &data[n..]
+}
+
+/// XMP fills fields the container format did not provide
+fn parse_xmp(packet: &[u8], meta: &mut DocumentMetadata) {
+    let xmp = String::from_utf8_lossy(packet);
+    let fields: [(&mut Option<String>, &str); 8] = [
+        (&mut meta.title, "dc:title"),
+        (&mut meta.author, "dc:creator"),
+        (&mut meta.software, "xmp:CreatorTool"),
+        (&mut meta.producer, "pdf:Producer"),
+        (&mut meta.created, "xmp:CreateDate"),
+        (&mut meta.modified, "xmp:ModifyDate"),
+        (&mut meta.camera_make, "tiff:Make"),
+        (&mut meta.camera_model, "tiff:Model"),
+    ];
+    for (field, name) in fields {
+        if field.is_none() {
+            *field = xml_value(&xmp, name);
+        }
+    }
+}
+
+fn parse_ooxml(data: &[u8], meta: &mut DocumentMetadata) {
+    let Ok(mut zip) = zip::ZipArchive::new(Cursor::new(data)) else { return };
+    if let Some(core) = zip_part(&mut zip, "docProps/core.xml") {
+        meta.title = xml_value(&core, "dc:title");
+        meta.author = xml_value(&core, "dc:creator");
+        meta.last_modified_by = xml_value(&core, "cp:lastModifiedBy");
+        meta.revision = xml_value(&core, "cp:revision");
+        meta.created = xml_value(&core, "dcterms:created");
+        meta.modified = xml_value(&core, "dcterms:modified");
+    }
+    if let Some(app) = zip_part(&mut zip, "docProps/app.xml") {
+        meta.software = xml_value(&app, "Application");
+        meta.pages = xml_value(&app, "Pages")
+            .or_else(|| xml_value(&app, "Slides"))
+            .and_then(|p| p.parse().ok());
+    }
+}
+
+fn zip_part<R: Read + std::io::Seek>(zip: &mut zip::ZipArchive<R>, name: &str) -> Option<String> {
+    let part = zip.by_name(name).ok()?;
+    if part.size() > MAX_PART_LEN {
+        return None;
+    }
+    let mut xml = Vec::new();
+    part.take(MAX_PART_LEN).read_to_end(&mut xml).ok()?;
+    Some(String::from_utf8_lossy(&xml).into_owned())
+}
+
+/// Text of the first `name` element, or of a `name="..."` attribute (XMP's
+/// short form); the first `rdf:li` of a list
+fn xml_value(doc: &str, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let mut from = 0;
+    while let Some(i) = doc[from..].find(&open) {
+        let start = from + i + open.len();
+        from = start;
+        let rest = &doc[start..];
+        if !rest.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            continue;
+        }
+        let gt = rest.find('>')?;
+        if rest[..gt].ends_with('/') {
+            continue;
+        }
+        let body = &rest[gt + 1..];
+        let body = &body[..body.find(&format!("</{}>", name))?];
+        if body.contains("<rdf:li") {
+            return xml_value(body, "rdf:li");
+        }
+        return clean(&xml_unescape(&strip_tags(body)));
+    }
+
+    let attr = format!("{}=", name);
+    let mut from = 0;
+    while let Some(i) = doc[from..].find(&attr) {
+        let at = from + i;
+        from = at + attr.len();
+        if !doc[..at].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let rest = &doc[from..];
+        let quote = rest.chars().next().filter(|&q| q == '"' || q == '\'')?;
+        let value = &rest[1..];
+        return clean(&xml_unescape(&value[..value.find(quote)?]));
+    }
+    None
+}
+
+fn strip_tags(fragment: &str) -> String {
+    let mut out = String::with_capacity(fragment.len());
+    let mut in_tag = false;
+    for c in fragment.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn xml_unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "quot" => '"',
+                "apos" => '\'',
+                entity => {
+                    let code = entity.strip_prefix('#')?;
+                    let code = match code.strip_prefix('x') {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => code.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, semi + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Trimmed and length-capped; `None` when blank
+fn clean(text: &str) -> Option<String> {
+    let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    (!text.is_empty()).then(|| text.chars().take(MAX_TEXT_LEN).collect())
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+fn round(x: f64, places: i32) -> f64 {
+    let scale = 10f64.powi(places);
+    (x * scale).round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Little-endian TIFF block: IFD0 with make, model and date, an EXIF IFD
+    /// with the original date and a GPS IFD at 48°51'29.6"N 2°17'40.2"W
+    fn exif_block() -> Vec<u8> {
+        let mut t = b"II*\0".to_vec();
+        t.extend(8u32.to_le_bytes());
+        let entry = |t: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+            t.extend(tag.to_le_bytes());
+            t.extend(kind.to_le_bytes());
+            t.extend(count.to_le_bytes());
+            t.extend(value.to_le_bytes());
+        };
+        // IFD0 at 8: 5 entries, data from 8 + 2 + 5 * 12 + 4 = 74
+        t.extend(5u16.to_le_bytes());
+        entry(&mut t, TAG_MAKE, TYPE_ASCII, 6, 74);
+        entry(&mut t, TAG_MODEL, TYPE_ASCII, 8, 80);
+        entry(&mut t, TAG_DATE_TIME, TYPE_ASCII, 20, 88);
+        entry(&mut t, TAG_EXIF_IFD, TYPE_LONG, 1, 108);
+        entry(&mut t, TAG_GPS_IFD, TYPE_LONG, 1, 140);
+        t.extend(0u32.to_le_bytes());
+        t.extend(b"Canon\0");
+        t.extend(b"EOS R5\0\0");
+        t.extend(b"2024:05:06 07:08:09\0");
+        // EXIF IFD at 108: 2 entries, data at 108 + 2 + 24 + 4 = 138
+        t.extend(2u16.to_le_bytes());
+        entry(&mut t, TAG_DATE_TIME_ORIGINAL, TYPE_ASCII, 20, 200);
+        entry(&mut t, TAG_PIXEL_WIDTH, TYPE_SHORT, 1, 640);
+        t.extend(0u32.to_le_bytes());
+        t.extend([0, 0]);
+        // GPS IFD at 140: 4 entries, rationals from 140 + 2 + 48 + 4 = 194
+        t.extend(4u16.to_le_bytes());
+        entry(&mut t, GPS_LATITUDE_REF, TYPE_ASCII, 2, u32::from_le_bytes(*b"N\0\0\0"));
+        entry(&mut t, GPS_LATITUDE, TYPE_RATIONAL, 3, 220);
+        entry(&mut t, GPS_LONGITUDE_REF, TYPE_ASCII, 2, u32::from_le_bytes(*b"W\0\0\0"));
+        entry(&mut t, GPS_LONGITUDE, TYPE_RATIONAL, 3, 244);
+        t.extend(0u32.to_le_bytes());
+        t.resize(200, 0);
+        t.extend(b"2024:05:06 07:00:00\0");
+        for (num, den) in [(48, 1), (51, 1), (296, 10), (2, 1), (17, 1), (402, 10)] {
+            t.extend((num as u32).to_le_bytes());
+            t.extend((den as u32).to_le_bytes());
+        }
+        t
+    }
+
+    fn jpeg_with_exif() -> Vec<u8> {
+        let exif = exif_block();
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend(((exif.len() + 8) as u16).to_be_bytes());
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(&exif);
+        // SOF0: precision, height 480, width 640, one component
+        jpeg.extend([0xFF, 0xC0, 0, 11, 8, 0x01, 0xE0, 0x02, 0x80, 1, 1, 0x11, 0]);
+        jpeg.extend([0xFF, 0xDA, 0, 2, 0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_jpeg_exif() {
+        let jpeg = jpeg_with_exif();
+        assert_eq!(format_of(&jpeg, Some("jpg"), None), Some(MetadataFormat::Jpeg));
+
+        let meta = extract(&jpeg, MetadataFormat::Jpeg).unwrap();
+        assert_eq!(meta.camera_make.as_deref(), Some("Canon"));
+        assert_eq!(meta.camera_model.as_deref(), Some("EOS R5"));
+        assert_eq!(meta.created.as_deref(), Some("2024-05-06T07:00:00"));
+        assert_eq!(meta.modified.as_deref(), Some("2024-05-06T07:08:09"));
+        assert_eq!((meta.width, meta.height), (Some(640), Some(480)));
+
+        let gps = meta.gps.unwrap();
+        assert_eq!(gps.latitude, 48.858222);
+        assert_eq!(gps.longitude, -2.294500);
+        assert_eq!(gps.altitude, None);
+
+        // A bare TIFF carries the same IFDs
+        let tiff = extract(&exif_block(), MetadataFormat::Tiff).unwrap();
+        assert_eq!(tiff.camera_model.as_deref(), Some("EOS R5"));
+        assert_eq!(format_of(&exif_block(), None, Some("tif")), Some(MetadataFormat::Tiff));
+    }
+
+    fn png_chunk(png: &mut Vec<u8>, kind: &[u8], body: &[u8]) {
+        png.extend((body.len() as u32).to_be_bytes());
+        png.extend(kind);
+        png.extend(body);
+        png.extend([0; 4]);
+    }
+
+    #[test]
+    fn test_png_chunks() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png_chunk(&mut png, b"IHDR", &[0, 0, 1, 0, 0, 0, 0, 200, 8, 6, 0, 0, 0]);
+        png_chunk(&mut png, b"tEXt", b"Software\0GIMP 2.10");
+        let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        z.write_all(b"a long comment").unwrap();
+        let mut ztxt = b"Comment\0\0".to_vec();
+        ztxt.extend(z.finish().unwrap());
+        png_chunk(&mut png, b"zTXt", &ztxt);
+        png_chunk(&mut png, b"iTXt", "Author\0\0\0en\0\0Zoë".as_bytes());
+        png_chunk(&mut png, b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<x:xmpmeta><rdf:Description xmp:CreateDate=\"2023-01-02T03:04:05Z\"/></x:xmpmeta>");
+        png_chunk(&mut png, b"IEND", b"");
+
+        let meta = extract(&png, MetadataFormat::Png).unwrap();
+        assert_eq!((meta.width, meta.height), (Some(256), Some(200)));
+        assert_eq!(meta.software.as_deref(), Some("GIMP 2.10"));
+        assert_eq!(meta.author.as_deref(), Some("Zoë"));
+        assert_eq!(meta.created.as_deref(), Some("2023-01-02T03:04:05Z"));
+        assert_eq!(meta.text.get("Comment").map(String::as_str), Some("a long comment"));
+        assert_eq!(meta.text.len(), 3);
+    }
+
+    #[test]
+    fn test_pdf_info_and_pages() {
+        let pdf = b"%PDF-1.7\n\
+            1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+            2 0 obj\n<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>\nendobj\n\
+            3 0 obj\n<< /Type /Page /Parent 2 0 R >>\nendobj\n\
+            4 0 obj\n<< /Type /Page /Parent 2 0 R >>\nendobj\n\
+            12 0 obj\n<< /Title (Q3 \\(draft\\)) /Author <FEFF0041006E006E0061> /Producer (pdfTeX-1.40)\n\
+            /CreationDate (D:20240102030405+01'00') /ModDate (D:20240103) >>\nendobj\n\
+            <x:xmpmeta><xmp:CreatorTool>LaTeX</xmp:CreatorTool><dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Ignored</rdf:li></rdf:Alt></dc:title></x:xmpmeta>\n\
+            trailer\n<< /Size 13 /Root 1 0 R /Info 12 0 R >>\n%%EOF\n";
+
+        let meta = extract(pdf, MetadataFormat::Pdf).unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Q3 (draft)"));
+        assert_eq!(meta.author.as_deref(), Some("Anna"));
+        assert_eq!(meta.producer.as_deref(), Some("pdfTeX-1.40"));
+        assert_eq!(meta.software.as_deref(), Some("LaTeX"));
+        assert_eq!(meta.created.as_deref(), Some("2024-01-02T03:04:05+01:00"));
+        assert_eq!(meta.modified.as_deref(), Some("2024-01-03T00:00:00"));
+        assert_eq!(meta.pages, Some(2));
+
+        // Page tree inside a compressed object stream
+        let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        z.write_all(b"2 0 << /Type /Pages /Kids [3 0 R] /Count 5 >>").unwrap();
+        let mut pdf = b"%PDF-1.5\n5 0 obj\n<< /Type /ObjStm /N 1 /First 4 /Filter /FlateDecode >>\nstream\n".to_vec();
+        pdf.extend(z.finish().unwrap());
+        pdf.extend(b"\nendstream\nendobj\n");
+        assert_eq!(extract(&pdf, MetadataFormat::Pdf).unwrap().pages, Some(5));
+    }
+
+    #[test]
+    fn test_pdf_malformed_dates() {
+        // Latin-1 \xe9 decodes to a two-byte character right after the zone sign
+        let pdf = b"%PDF-1.4\n1 0 obj\n<< /CreationDate (D:20240101120000+0\xe9) /ModDate (D:2024\xe9) >>\nendobj\n\
+            trailer\n<< /Info 1 0 R >>\n%%EOF\n";
+        let meta = extract(pdf, MetadataFormat::Pdf).unwrap();
+        assert_eq!(meta.created.as_deref(), Some("2024-01-01T12:00:00"));
+        assert_eq!(meta.modified.as_deref(), Some("2024-01-01T00:00:00"));
+
+        assert_eq!(pdf_date("D:20240101120000-0530"), "2024-01-01T12:00:00-05:30");
+        assert_eq!(pdf_date("D:20240101120000+\u{e9}\u{e9}"), "2024-01-01T12:00:00");
+        assert_eq!(pdf_date("D:2024\u{e9}"), "2024-01-01T00:00:00");
+    }
+
+    #[test]
+    fn test_ooxml_doc_props() {
+        use zip::write::SimpleFileOptions;
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("[Content_Types].xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"<Types/>").unwrap();
+        zip.start_file("docProps/core.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"<cp:coreProperties><dc:creator>J. Smith &amp; Co</dc:creator>\
+            <cp:lastModifiedBy>ops</cp:lastModifiedBy><cp:revision>7</cp:revision>\
+            <dcterms:created xsi:type=\"dcterms:W3CDTF\">2021-03-04T05:06:07Z</dcterms:created>\
+            <dc:title/></cp:coreProperties>").unwrap();
+        zip.start_file("docProps/app.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"<Properties><Application>Microsoft Office Word</Application><Pages>12</Pages></Properties>").unwrap();
+        let data = zip.finish().unwrap().into_inner();
+
+        assert_eq!(format_of(&data, Some("zip"), Some("docx")), Some(MetadataFormat::Ooxml));
+        let meta = extract(&data, MetadataFormat::Ooxml).unwrap();
+        assert_eq!(meta.author.as_deref(), Some("J. Smith & Co"));
+        assert_eq!(meta.last_modified_by.as_deref(), Some("ops"));
+        assert_eq!(meta.revision.as_deref(), Some("7"));
+        assert_eq!(meta.created.as_deref(), Some("2021-03-04T05:06:07Z"));
+        assert_eq!(meta.title, None);
+        assert_eq!(meta.software.as_deref(), Some("Microsoft Office Word"));
+        assert_eq!(meta.pages, Some(12));
+
+        assert!(extract(b"PK\x03\x04 truncated", MetadataFormat::Ooxml).is_none());
+        assert!(extract(&[0xFF, 0xD8, 0xFF, 0xE1, 0xFF], MetadataFormat::Jpeg).is_none());
+    }
+}
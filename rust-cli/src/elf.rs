@@ -0,0 +1,438 @@
+//! ELF Metadata Module
+//!
+//! Architecture, linking and checksec-style hardening flags of ELF
+//! executables and shared objects: PIE, RELRO, NX, stack canary, stripped,
+//! interpreter, `DT_NEEDED` libraries and GNU build-id. Parsing works on an
+//! in-memory image and tolerates truncated or malformed files by reporting
+//! whatever could be read.
+
+use serde::{Deserialize, Serialize};
+
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const PT_INTERP: u32 = 3;
+const PT_NOTE: u32 = 4;
+const PT_GNU_STACK: u32 = 0x6474_E551;
+const PT_GNU_RELRO: u32 = 0x6474_E552;
+const PF_X: u32 = 1;
+
+/// Program and section header sizes
+const PHDR32_SIZE: u64 = 32;
+const PHDR64_SIZE: u64 = 56;
+const SHDR32_SIZE: u64 = 40;
+const SHDR64_SIZE: u64 = 64;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNSYM: u32 = 11;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_BIND_NOW: u64 = 24;
+const DT_FLAGS: u64 = 30;
+const DT_FLAGS_1: u64 = 0x6FFF_FFFB;
+const DF_BIND_NOW: u64 = 0x8;
+const DF_1_NOW: u64 = 0x1;
+const DF_1_PIE: u64 = 0x0800_0000;
+
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// Symbols whose presence means the binary was built with stack protection
+const CANARY_SYMBOLS: &[&str] = &["__stack_chk_fail", "__stack_chk_guard", "__intel_security_cookie"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relro {
+    None,
+    Partial,
+    Full,
+}
+
+/// Metadata and hardening flags of an ELF file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElfInfo {
+    /// `x86_64`, `aarch64`, `arm`... or `unknown(<e_machine>)`
+    pub arch: String,
+    pub bits: u8,
+    pub big_endian: bool,
+    /// `executable`, `shared`, `relocatable`, `core` or `unknown`
+    pub elf_type: String,
+    pub pie: bool,
+    pub relro: Relro,
+    /// Non-executable stack (`PT_GNU_STACK` without `PF_X`)
+    pub nx: bool,
+    pub canary: bool,
+    /// No `.symtab` section
+    pub stripped: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+    /// `DT_NEEDED` libraries in load order
+    #[serde(default)]
+    pub needed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+}
+
+/// Bounds-checked reads in the file's byte order and word size
+struct Image<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+    is_64: bool,
+}
+
+impl Image<'_> {
+    fn bytes<const N: usize>(&self, offset: u64) -> Option<[u8; N]> {
+        let start = usize::try_from(offset).ok()?;
+        self.data.get(start..start.checked_add(N)?)?.try_into().ok()
+    }
+
+    fn u16(&self, offset: u64) -> Option<u16> {
+        let b = self.bytes(offset)?;
+        Some(if self.big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    }
+
+    fn u32(&self, offset: u64) -> Option<u32> {
+        let b = self.bytes(offset)?;
+        Some(if self.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    }
+
+    fn u64(&self, offset: u64) -> Option<u64> {
+        let b = self.bytes(offset)?;
+        Some(if self.big_endian { u64::from_be_bytes(b) } else { u64::from_le_bytes(b) })
+    }
+
+    /// Address-sized field: 4 bytes in ELF32, 8 in ELF64
+    fn word(&self, offset: u64) -> Option<u64> {
+        if self.is_64 { self.u64(offset) } else { self.u32(offset).map(u64::from) }
+    }
+
+    fn word_size(&self) -> u64 {
+        if self.is_64 { 8 } else { 4 }
+    }
+
+    /// NUL-terminated string at `offset`
+    fn cstr(&self, offset: u64) -> Option<String> {
+        let rest = self.data.get(usize::try_from(offset).ok()?..)?;
+        let end = rest.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+
+    fn slice(&self, offset: u64, len: u64) -> Option<&[u8]> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(len).ok()?)?;
+        self.data.get(start..end)
+    }
+}
+
+struct Segment {
+    kind: u32,
+    flags: u32,
+    offset: u64,
+    vaddr: u64,
+    filesz: u64,
+}
+
+struct Section {
+    kind: u32,
+    offset: u64,
+    size: u64,
+    link: u32,
+    entsize: u64,
+}
+
+/// Parse an ELF image; `None` unless it starts with a valid ELF header
+pub fn parse(data: &[u8]) -> Option<ElfInfo> {
+    if !data.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let is_64 = match data.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = match data.get(5)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let elf = Image { data, big_endian, is_64 };
+
+    let e_type = elf.u16(16)?;
+    let machine = elf.u16(18)?;
+    let (phoff, shoff, rest) = if is_64 {
+        (elf.u64(32)?, elf.u64(40)?, 52)
+    } else {
+        (u64::from(elf.u32(28)?), u64::from(elf.u32(32)?), 40)
+    };
+    let phentsize = u64::from(elf.u16(rest + 2)?);
+    let phnum = u64::from(elf.u16(rest + 4)?);
+    let shentsize = u64::from(elf.u16(rest + 6)?);
+    let shnum = u64::from(elf.u16(rest + 8)?);
+
+    let segments: Vec<Segment> = (0..phnum)
+        .map_while(|i| read_segment(&elf, phoff.saturating_add(i * phentsize)))
+        .collect();
+    let sections: Vec<Section> = if shoff == 0 {
+        Vec::new()
+    } else {
+        (0..shnum).map_while(|i| read_section(&elf, shoff.saturating_add(i * shentsize))).collect()
+    };
+
+    let interpreter = segments.iter()
+        .find(|s| s.kind == PT_INTERP)
+        .and_then(|s| elf.cstr(s.offset));
+    let dynamic = read_dynamic(&elf, &segments);
+
+    let bind_now = dynamic.bind_now
+        || dynamic.flags & DF_BIND_NOW != 0
+        || dynamic.flags_1 & DF_1_NOW != 0;
+    let relro = match (segments.iter().any(|s| s.kind == PT_GNU_RELRO), bind_now) {
+        (false, _) => Relro::None,
+        (true, false) => Relro::Partial,
+        (true, true) => Relro::Full,
+    };
+
+    Some(ElfInfo {
+        arch: arch_name(machine),
+        bits: if is_64 { 64 } else { 32 },
+        big_endian,
+        elf_type: match e_type {
+            1 => "relocatable",
+            ET_EXEC => "executable",
+            ET_DYN if interpreter.is_some() || dynamic.flags_1 & DF_1_PIE != 0 => "executable",
+            ET_DYN => "shared",
+            4 => "core",
+            _ => "unknown",
+        }
+        .to_string(),
+        pie: e_type == ET_DYN && (interpreter.is_some() || dynamic.flags_1 & DF_1_PIE != 0),
+        relro,
+        nx: segments.iter().any(|s| s.kind == PT_GNU_STACK && s.flags & PF_X == 0),
+        canary: has_canary(&elf, &sections),
+        stripped: !sections.iter().any(|s| s.kind == SHT_SYMTAB),
+        interpreter,
+        needed: dynamic.needed,
+        build_id: read_build_id(&elf, &segments),
+    })
+}
+
+fn read_segment(elf: &Image, at: u64) -> Option<Segment> {
+    // Offsets come from the file; the fields below must not wrap past u64::MAX
+    at.checked_add(if elf.is_64 { PHDR64_SIZE } else { PHDR32_SIZE })?;
+    if elf.is_64 {
+        Some(Segment {
+            kind: elf.u32(at)?,
+            flags: elf.u32(at + 4)?,
+            offset: elf.u64(at + 8)?,
+            vaddr: elf.u64(at + 16)?,
+            filesz: elf.u64(at + 32)?,
+        })
+    } else {
+        Some(Segment {
+            kind: elf.u32(at)?,
+            offset: u64::from(elf.u32(at + 4)?),
+            vaddr: u64::from(elf.u32(at + 8)?),
+            filesz: u64::from(elf.u32(at + 16)?),
+            flags: elf.u32(at + 24)?,
+        })
+    }
+}
+
+fn read_section(elf: &Image, at: u64) -> Option<Section> {
+    at.checked_add(if elf.is_64 { SHDR64_SIZE } else { SHDR32_SIZE })?;
+    let kind = elf.u32(at + 4)?;
+    if elf.is_64 {
+        Some(Section {
+            kind,
+            offset: elf.u64(at + 24)?,
+            size: elf.u64(at + 32)?,
+            link: elf.u32(at + 40)?,
+            entsize: elf.u64(at + 56)?,
+        })
+    } else {
+        Some(Section {
+            kind,
+            offset: u64::from(elf.u32(at + 16)?),
+            size: u64::from(elf.u32(at + 20)?),
+            link: elf.u32(at + 24)?,
+            entsize: u64::from(elf.u32(at + 36)?),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Dynamic {
+    needed: Vec<String>,
+    bind_now: bool,
+    flags: u64,
+    flags_1: u64,
+}
+
+fn read_dynamic(elf: &Image, segments: &[Segment]) -> Dynamic {
+    let mut dynamic = Dynamic::default();
+    let Some(segment) = segments.iter().find(|s| s.kind == PT_DYNAMIC) else {
+        return dynamic;
+    };
+
+    if elf.slice(segment.offset, segment.filesz).is_none() {
+        return dynamic;
+    }
+    let entry = elf.word_size() * 2;
+    let mut needed_offsets = Vec::new();
+    let mut strtab = None;
+    for i in 0..segment.filesz / entry {
+        let at = segment.offset + i * entry;
+        let (Some(tag), Some(value)) = (elf.word(at), elf.word(at + elf.word_size())) else {
+            break;
+        };
+        match tag {
+            DT_NULL => break,
+            DT_NEEDED => needed_offsets.push(value),
+            DT_STRTAB => strtab = Some(value),
+            DT_BIND_NOW => dynamic.bind_now = true,
+            DT_FLAGS => dynamic.flags = value,
+            DT_FLAGS_1 => dynamic.flags_1 = value,
+            _ => {}
+        }
+    }
+
+    // DT_STRTAB is a virtual address; map it to a file offset through PT_LOAD
+    let strtab = strtab.and_then(|addr| {
+        segments.iter()
+            .find(|s| s.kind == PT_LOAD && addr >= s.vaddr && addr - s.vaddr < s.filesz)
+            .map(|s| s.offset.saturating_add(addr - s.vaddr))
+    });
+    if let Some(strtab) = strtab {
+        dynamic.needed = needed_offsets.iter()
+            .filter_map(|&name| elf.cstr(strtab.saturating_add(name)))
+            .collect();
+    }
+    dynamic
+}
+
+/// Stack-protector symbols in `.symtab` or `.dynsym`
+fn has_canary(elf: &Image, sections: &[Section]) -> bool {
+    sections.iter()
+        .filter(|s| (s.kind == SHT_SYMTAB || s.kind == SHT_DYNSYM) && s.entsize > 0)
+        // Sizes come from the file; only trust tables that fit in it
+        .filter(|s| elf.slice(s.offset, s.size).is_some())
+        .any(|symtab| {
+            let Some(strtab) = sections.get(symtab.link as usize) else {
+                return false;
+            };
+            (0..symtab.size / symtab.entsize).any(|i| {
+                // st_name is the first field in both ELF32 and ELF64 symbols
+                elf.u32(symtab.offset + i * symtab.entsize)
+                    .filter(|&name| u64::from(name) < strtab.size)
+                    .and_then(|name| elf.cstr(strtab.offset.saturating_add(u64::from(name))))
+                    .is_some_and(|name| CANARY_SYMBOLS.contains(&name.as_str()))
+            })
+        })
+}
+
+/// `NT_GNU_BUILD_ID` from the `PT_NOTE` segments, as hex
+fn read_build_id(elf: &Image, segments: &[Segment]) -> Option<String> {
+    let align4 = |n: u64| n.div_ceil(4) * 4;
+    for segment in segments.iter().filter(|s| s.kind == PT_NOTE) {
+        let notes = Image { data: elf.slice(segment.offset, segment.filesz)?, ..*elf };
+        let mut at = 0;
+        while let (Some(namesz), Some(descsz), Some(kind)) = (notes.u32(at), notes.u32(at + 4), notes.u32(at + 8)) {
+            let name_at = at + 12;
+            let desc_at = name_at + align4(u64::from(namesz));
+            if kind == NT_GNU_BUILD_ID && notes.slice(name_at, u64::from(namesz)) == Some(b"GNU\0") {
+                let desc = notes.slice(desc_at, u64::from(descsz))?;
+                return Some(desc.iter().map(|b| format!("{:02x}", b)).collect());
+            }
+            at = desc_at + align4(u64::from(descsz));
+        }
+    }
+    None
+}
+
+fn arch_name(machine: u16) -> String {
+    match machine {
+        3 => "x86",
+        8 => "mips",
+        20 => "powerpc",
+        21 => "powerpc64",
+        22 => "s390",
+        40 => "arm",
+        43 => "sparcv9",
+        62 => "x86_64",
+        183 => "aarch64",
+        243 => "riscv",
+        258 => "loongarch",
+        _ => return format!("unknown({})", machine),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimal_header() {
+        // 32-bit big-endian MIPS executable with no segments or sections
+        let mut header = vec![0u8; 52];
+        header[..6].copy_from_slice(b"\x7fELF\x01\x02");
+        header[16..18].copy_from_slice(&ET_EXEC.to_be_bytes());
+        header[18..20].copy_from_slice(&8u16.to_be_bytes());
+
+        let elf = parse(&header).unwrap();
+        assert_eq!((elf.arch.as_str(), elf.bits, elf.big_endian), ("mips", 32, true));
+        assert_eq!(elf.elf_type, "executable");
+        assert!(!elf.pie && !elf.nx && !elf.canary);
+        assert!(elf.stripped);
+        assert_eq!(elf.relro, Relro::None);
+        assert!(elf.needed.is_empty() && elf.build_id.is_none());
+
+        assert!(parse(b"\x7fELF\x03").is_none());
+        assert!(parse(&header[..10]).is_none());
+        assert!(parse(b"MZ").is_none());
+    }
+
+    #[test]
+    fn test_malformed_header_offsets() {
+        // 64-bit little-endian x86_64 with header tables at the end of the address space
+        let mut header = vec![0u8; 128];
+        header[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        header[16..18].copy_from_slice(&ET_DYN.to_le_bytes());
+        header[18..20].copy_from_slice(&62u16.to_le_bytes());
+        header[32..40].copy_from_slice(&u64::MAX.to_le_bytes()); // e_phoff
+        header[40..48].copy_from_slice(&u64::MAX.to_le_bytes()); // e_shoff
+        header[54..56].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        header[56..58].copy_from_slice(&3u16.to_le_bytes()); // e_phnum
+        header[58..60].copy_from_slice(&64u16.to_le_bytes()); // e_shentsize
+        header[60..62].copy_from_slice(&3u16.to_le_bytes()); // e_shnum
+
+        let elf = parse(&header).unwrap();
+        assert_eq!(elf.arch, "x86_64");
+        assert_eq!(elf.elf_type, "shared");
+        assert!(elf.stripped && !elf.nx);
+
+        // Tables that start inside the file but run past its end
+        header[32..40].copy_from_slice(&100u64.to_le_bytes());
+        header[40..48].copy_from_slice(&120u64.to_le_bytes());
+        let elf = parse(&header).unwrap();
+        assert!(elf.stripped && elf.interpreter.is_none());
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    #[test]
+    fn test_parse_own_executable() {
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let elf = parse(&data).unwrap();
+
+        assert_eq!(elf.arch, std::env::consts::ARCH);
+        assert_eq!(elf.bits as usize, usize::BITS as usize);
+        assert_eq!(elf.elf_type, "executable");
+        assert!(elf.pie);
+        assert!(elf.nx);
+        assert!(elf.interpreter.is_some_and(|i| i.contains("ld-linux")));
+        assert!(elf.needed.iter().any(|lib| lib.starts_with("libc.so")));
+    }
+}
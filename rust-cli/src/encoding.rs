@@ -0,0 +1,461 @@
+//! Text Encoding Module
+//!
+//! Detects how a text file is encoded (BOM, UTF-8, UTF-16 LE/BE, or a legacy
+//! 8-bit code page) and decodes it incrementally, so counts are taken on the
+//! characters the file actually contains rather than a lossy UTF-8 reading.
+//! [`LineStats`] then reports line endings, trailing whitespace and whether
+//! the file ends with a newline.
+
+use serde::{Deserialize, Serialize};
+
+use crate::sniff;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// Share of high (or low) bytes that must be zero for BOM-less UTF-16
+const UTF16_ZERO_SHARE: f64 = 0.3;
+
+/// Windows-1252 code points for bytes 0x80-0x9F; unassigned bytes map to
+/// the matching C1 control, as browsers do
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[serde(rename = "ascii")]
+    Ascii,
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Legacy 8-bit text that is not valid UTF-8
+    #[serde(rename = "windows-1252")]
+    Windows1252,
+}
+
+impl TextEncoding {
+    fn bom(self) -> &'static [u8] {
+        match self {
+            TextEncoding::Ascii | TextEncoding::Windows1252 => b"",
+            TextEncoding::Utf8 => UTF8_BOM,
+            TextEncoding::Utf16Le => UTF16LE_BOM,
+            TextEncoding::Utf16Be => UTF16BE_BOM,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// No line breaks at all
+    None,
+    Lf,
+    Crlf,
+    /// Classic Mac OS lone `\r`
+    Cr,
+    Mixed,
+}
+
+/// Encoding and line-ending report for a text file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextInfo {
+    pub encoding: TextEncoding,
+    /// Starts with a byte order mark
+    pub bom: bool,
+    pub line_ending: LineEnding,
+    pub crlf_count: u64,
+    pub lf_count: u64,
+    pub cr_count: u64,
+    /// Lines ending in a space or tab
+    pub trailing_whitespace_lines: u64,
+    pub final_newline: bool,
+    /// Byte sequences that did not decode and were counted as U+FFFD
+    #[serde(default)]
+    pub invalid_sequences: u64,
+}
+
+/// What the header says about a file's text encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detected {
+    /// `Utf8` without a BOM is tentative: the content may turn out to be legacy 8-bit
+    pub encoding: TextEncoding,
+    pub bom: bool,
+}
+
+/// Detect the encoding from a file header; `None` means binary
+pub fn detect(head: &[u8]) -> Option<Detected> {
+    for encoding in [TextEncoding::Utf8, TextEncoding::Utf16Le, TextEncoding::Utf16Be] {
+        if head.starts_with(encoding.bom()) {
+            return Some(Detected { encoding, bom: true });
+        }
+    }
+    if let Some(encoding) = guess_utf16(head) {
+        return Some(Detected { encoding, bom: false });
+    }
+    if sniff::is_binary(head) {
+        return None;
+    }
+    Some(Detected { encoding: TextEncoding::Utf8, bom: false })
+}
+
+/// BOM-less UTF-16 shows up as a zero in every other byte of mostly-ASCII text
+fn guess_utf16(head: &[u8]) -> Option<TextEncoding> {
+    let pairs: Vec<&[u8]> = head.chunks_exact(2).take(sniff::SNIFF_LEN / 2).collect();
+    if pairs.len() < 2 || pairs.iter().any(|p| p == &[0, 0]) {
+        return None;
+    }
+    let share = |zeros: usize| zeros as f64 / pairs.len() as f64;
+    let even = share(pairs.iter().filter(|p| p[0] == 0).count());
+    let odd = share(pairs.iter().filter(|p| p[1] == 0).count());
+
+    if odd >= UTF16_ZERO_SHARE && even == 0.0 {
+        Some(TextEncoding::Utf16Le)
+    } else if even >= UTF16_ZERO_SHARE && odd == 0.0 {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Decode a whole buffer, dropping any BOM
+pub fn decode(bytes: &[u8], encoding: TextEncoding) -> String {
+    let bom = !encoding.bom().is_empty() && bytes.starts_with(encoding.bom());
+    let mut decoder = Decoder::new(encoding, bom);
+    let mut out = String::with_capacity(bytes.len());
+    decoder.decode(bytes, |c| out.push(c));
+    decoder.finish(|c| out.push(c));
+    out
+}
+
+/// Detect and decode a buffer; 8-bit text that is not UTF-8 is read as
+/// Windows-1252. `None` for binary content
+pub fn decode_text(bytes: &[u8]) -> Option<String> {
+    let detected = detect(bytes)?;
+    if detected.encoding == TextEncoding::Utf8 && !detected.bom {
+        // A multi-byte sequence cut off at the end of a preview is still UTF-8
+        let valid = match std::str::from_utf8(bytes) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        };
+        if !valid {
+            return Some(decode(bytes, TextEncoding::Windows1252));
+        }
+    }
+    Some(decode(bytes, detected.encoding))
+}
+
+/// Incremental decoder; chunks may split multi-byte sequences anywhere
+pub struct Decoder {
+    encoding: TextEncoding,
+    bom: bool,
+    /// BOM bytes still to drop from the start of the stream
+    skip: usize,
+    /// Incomplete sequence carried into the next chunk
+    carry: Vec<u8>,
+    /// UTF-16 high surrogate waiting for its pair
+    high_surrogate: Option<u16>,
+    invalid: u64,
+    non_ascii: bool,
+}
+
+impl Decoder {
+    pub fn new(encoding: TextEncoding, bom: bool) -> Self {
+        Self {
+            encoding,
+            bom,
+            skip: if bom { encoding.bom().len() } else { 0 },
+            carry: Vec::new(),
+            high_surrogate: None,
+            invalid: 0,
+            non_ascii: false,
+        }
+    }
+
+    pub fn decode(&mut self, mut chunk: &[u8], mut emit: impl FnMut(char)) {
+        let skipped = self.skip.min(chunk.len());
+        self.skip -= skipped;
+        chunk = &chunk[skipped..];
+        if chunk.is_empty() {
+            return;
+        }
+        self.non_ascii |= !chunk.is_ascii();
+
+        match self.encoding {
+            TextEncoding::Ascii | TextEncoding::Utf8 => self.decode_utf8(chunk, &mut emit),
+            TextEncoding::Utf16Le => self.decode_utf16(chunk, u16::from_le_bytes, &mut emit),
+            TextEncoding::Utf16Be => self.decode_utf16(chunk, u16::from_be_bytes, &mut emit),
+            TextEncoding::Windows1252 => {
+                for &b in chunk {
+                    emit(match b {
+                        0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                        _ => b as char,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Flush an incomplete trailing sequence as U+FFFD
+    pub fn finish(&mut self, mut emit: impl FnMut(char)) {
+        if !self.carry.is_empty() || self.high_surrogate.is_some() {
+            self.carry.clear();
+            self.high_surrogate = None;
+            self.replace(&mut emit);
+        }
+    }
+
+    /// The encoding the content turned out to have
+    pub fn encoding(&self) -> TextEncoding {
+        match self.encoding {
+            TextEncoding::Utf8 if !self.non_ascii && !self.bom => TextEncoding::Ascii,
+            encoding => encoding,
+        }
+    }
+
+    pub fn bom(&self) -> bool {
+        self.bom
+    }
+
+    pub fn invalid(&self) -> u64 {
+        self.invalid
+    }
+
+    fn replace(&mut self, emit: &mut impl FnMut(char)) {
+        self.invalid += 1;
+        emit('\u{FFFD}');
+    }
+
+    fn decode_utf8(&mut self, chunk: &[u8], emit: &mut impl FnMut(char)) {
+        let joined;
+        let mut rest = if self.carry.is_empty() {
+            chunk
+        } else {
+            joined = [std::mem::take(&mut self.carry).as_slice(), chunk].concat();
+            joined.as_slice()
+        };
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    valid.chars().for_each(&mut *emit);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    // Cannot fail: `valid_up_to` bytes are valid UTF-8
+                    std::str::from_utf8(valid).unwrap_or_default().chars().for_each(&mut *emit);
+                    match e.error_len() {
+                        Some(len) => {
+                            self.replace(emit);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            self.carry = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn decode_utf16(&mut self, chunk: &[u8], unit: fn([u8; 2]) -> u16, emit: &mut impl FnMut(char)) {
+        let joined;
+        let bytes = if self.carry.is_empty() {
+            chunk
+        } else {
+            joined = [std::mem::take(&mut self.carry).as_slice(), chunk].concat();
+            joined.as_slice()
+        };
+
+        let mut pairs = bytes.chunks_exact(2);
+        for pair in &mut pairs {
+            let u = unit([pair[0], pair[1]]);
+            match (self.high_surrogate.take(), u) {
+                (Some(high), 0xDC00..=0xDFFF) => {
+                    let c = 0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(u) - 0xDC00);
+                    emit(char::from_u32(c).unwrap_or('\u{FFFD}'));
+                }
+                (pending, _) => {
+                    if pending.is_some() {
+                        self.replace(emit);
+                    }
+                    match u {
+                        0xD800..=0xDBFF => self.high_surrogate = Some(u),
+                        0xDC00..=0xDFFF => self.replace(emit),
+                        _ => emit(char::from_u32(u32::from(u)).unwrap_or('\u{FFFD}')),
+                    }
+                }
+            }
+        }
+        self.carry = pairs.remainder().to_vec();
+    }
+}
+
+/// Line-ending, trailing-whitespace and final-newline tally over decoded text
+#[derive(Debug, Default)]
+pub struct LineStats {
+    crlf: u64,
+    lf: u64,
+    cr: u64,
+    trailing_whitespace: u64,
+    /// Last character of the current line, terminators excluded
+    last_in_line: Option<char>,
+    /// A `\r` whose meaning depends on the next character
+    pending_cr: bool,
+    last: Option<char>,
+}
+
+impl LineStats {
+    pub fn push(&mut self, c: char) {
+        match c {
+            '\n' if self.pending_cr => {
+                self.crlf += 1;
+                self.pending_cr = false;
+            }
+            '\n' => {
+                self.lf += 1;
+                self.end_line();
+            }
+            '\r' => {
+                if self.pending_cr {
+                    self.cr += 1;
+                }
+                self.pending_cr = true;
+                self.end_line();
+            }
+            c => {
+                if std::mem::take(&mut self.pending_cr) {
+                    self.cr += 1;
+                }
+                self.last_in_line = Some(c);
+            }
+        }
+        self.last = Some(c);
+    }
+
+    fn end_line(&mut self) {
+        if matches!(self.last_in_line.take(), Some(' ' | '\t')) {
+            self.trailing_whitespace += 1;
+        }
+    }
+
+    pub fn finish(mut self, encoding: TextEncoding, bom: bool, invalid_sequences: u64) -> TextInfo {
+        if self.pending_cr {
+            self.cr += 1;
+        }
+        self.end_line();
+
+        let line_ending = match (self.crlf > 0, self.lf > 0, self.cr > 0) {
+            (false, false, false) => LineEnding::None,
+            (true, false, false) => LineEnding::Crlf,
+            (false, true, false) => LineEnding::Lf,
+            (false, false, true) => LineEnding::Cr,
+            _ => LineEnding::Mixed,
+        };
+
+        TextInfo {
+            encoding,
+            bom,
+            line_ending,
+            crlf_count: self.crlf,
+            lf_count: self.lf,
+            cr_count: self.cr,
+            trailing_whitespace_lines: self.trailing_whitespace,
+            final_newline: matches!(self.last, Some('\n' | '\r')),
+            invalid_sequences,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn lines(text: &str) -> TextInfo {
+        let mut stats = LineStats::default();
+        text.chars().for_each(|c| stats.push(c));
+        stats.finish(TextEncoding::Utf8, false, 0)
+    }
+
+    #[test]
+    fn test_detect() {
+        let d = |bytes: &[u8]| detect(bytes).map(|d| (d.encoding, d.bom));
+        assert_eq!(d(b"\xEF\xBB\xBFhi"), Some((TextEncoding::Utf8, true)));
+        assert_eq!(d(b"\xFF\xFEh\0i\0"), Some((TextEncoding::Utf16Le, true)));
+        assert_eq!(d(b"\xFE\xFF\0h\0i"), Some((TextEncoding::Utf16Be, true)));
+        assert_eq!(d(&utf16le("hello")), Some((TextEncoding::Utf16Le, false)));
+        assert_eq!(d(b"\0h\0e\0l\0l\0o"), Some((TextEncoding::Utf16Be, false)));
+        assert_eq!(d(b"plain"), Some((TextEncoding::Utf8, false)));
+        assert_eq!(d(b"\x7fELF\x02\x01\x01\0\0\0\0"), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut bytes = UTF16LE_BOM.to_vec();
+        bytes.extend(utf16le("héllo 😀"));
+        assert_eq!(decode(&bytes, TextEncoding::Utf16Le), "héllo 😀");
+        assert_eq!(decode(b"\xEF\xBB\xBFhi", TextEncoding::Utf8), "hi");
+        assert_eq!(decode(b"caf\xe9 \x93ok\x94", TextEncoding::Windows1252), "café \u{201C}ok\u{201D}");
+
+        assert_eq!(decode_text(b"caf\xe9 ok").as_deref(), Some("café ok"));
+        assert_eq!(decode_text("café".as_bytes()).as_deref(), Some("café"));
+        assert_eq!(decode_text(b"\x7fELF\0"), None);
+    }
+
+    #[test]
+    fn test_decoder_across_chunks() {
+        let bytes = utf16le("a😀b");
+        let mut decoder = Decoder::new(TextEncoding::Utf16Le, false);
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            decoder.decode(chunk, |c| out.push(c));
+        }
+        decoder.finish(|c| out.push(c));
+        assert_eq!(out, "a😀b");
+        assert_eq!(decoder.invalid(), 0);
+
+        let mut decoder = Decoder::new(TextEncoding::Utf8, false);
+        decoder.decode(b"ok\xe2\x82", |_| {});
+        let mut tail = String::new();
+        decoder.finish(|c| tail.push(c));
+        assert_eq!((tail.as_str(), decoder.invalid()), ("\u{FFFD}", 1));
+        assert_eq!(decoder.encoding(), TextEncoding::Utf8);
+        assert_eq!(Decoder::new(TextEncoding::Utf8, false).encoding(), TextEncoding::Ascii);
+    }
+
+    #[test]
+    fn test_line_stats() {
+        let unix = lines("one\ntwo \nthree\n");
+        assert_eq!(unix.line_ending, LineEnding::Lf);
+        assert_eq!(unix.trailing_whitespace_lines, 1);
+        assert!(unix.final_newline);
+
+        let dos = lines("one\r\ntwo\t\r\nthree");
+        assert_eq!(dos.line_ending, LineEnding::Crlf);
+        assert_eq!(dos.crlf_count, 2);
+        assert_eq!(dos.trailing_whitespace_lines, 1);
+        assert!(!dos.final_newline);
+
+        let mixed = lines("a\r\nb\nc\rd \r");
+        assert_eq!(mixed.line_ending, LineEnding::Mixed);
+        assert_eq!((mixed.crlf_count, mixed.lf_count, mixed.cr_count), (1, 1, 2));
+        assert_eq!(mixed.trailing_whitespace_lines, 1);
+        assert!(mixed.final_newline);
+
+        assert_eq!(lines("").line_ending, LineEnding::None);
+        assert_eq!(lines("last ").trailing_whitespace_lines, 1);
+    }
+}
@@ -0,0 +1,567 @@
+//! Analysis Engine Module
+//!
+//! The per-file analysis behind every front end: the CLI walker, TUI, GUI,
+//! IPC plugin and WASM bindings all produce a [`FileInfo`] from here. A single
+//! streaming pass sniffs the header, hashes the content, profiles its byte
+//! entropy and, for text files, detects the encoding and counts lines, words
+//! and characters on the decoded text. Images and documents also get their
+//! embedded metadata extracted.
+
+use std::fs::{File, Metadata};
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::archive::{ArchiveEntry, ArchiveInfo};
+use crate::docmeta::{self, DocumentMetadata};
+use crate::encoding::{self, Decoder, LineStats, TextEncoding, TextInfo};
+use crate::elf::{self, ElfInfo};
+use crate::entropy::{self, EntropyCounter, EntropyProfile};
+use crate::hasher::{Digests, HashAlgorithm, MultiHasher};
+use crate::markdown::{self, MarkdownInfo};
+use crate::ownership::Ownership;
+use crate::sloc::{self, SourceLines};
+use crate::sniff::{self, ContentType};
+use crate::storage::StorageInfo;
+use crate::timestamps;
+use crate::xattrs::{self, ExtendedAttributes};
+
+/// Read buffer size for the content pass
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Largest Markdown document parsed for structure
+const MARKDOWN_MAX_LEN: usize = 16 * 1024 * 1024;
+
+/// Largest ELF file parsed for headers and hardening flags
+const ELF_MAX_LEN: usize = 64 * 1024 * 1024;
+
+/// Largest source file counted for code, comment and blank lines
+const SOURCE_MAX_LEN: usize = 16 * 1024 * 1024;
+
+/// Largest image or document parsed for embedded metadata
+const DOCUMENT_MAX_LEN: usize = 64 * 1024 * 1024;
+
+/// Default content-read limit, matching `[analysis] max_file_size`
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Analysis result for one file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub path: String,
+    pub size: u64,
+    /// Lowercase extension, else the sniffed format, else `unknown`
+    pub file_type: String,
+    /// Sniffed MIME type, confidence and extension mismatch flag
+    #[serde(flatten)]
+    pub content_type: ContentType,
+    /// Last content modification (mtime), RFC 3339 UTC
+    #[serde(default)]
+    pub modified: Option<String>,
+    /// Last access (atime), RFC 3339 UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<String>,
+    /// Last inode change (ctime, Unix), RFC 3339 UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed: Option<String>,
+    /// Birth time where the filesystem records it (`statx` on Linux), RFC 3339 UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Octal permission bits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+    /// Owner, group and setuid/setgid/sticky bits (Unix)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ownership: Option<Ownership>,
+    /// Inode, link count and allocated blocks (Unix)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<StorageInfo>,
+    /// SELinux label, file capabilities, ACLs and other extended attributes;
+    /// only collected when enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xattrs: Option<ExtendedAttributes>,
+    /// Digest of the first configured hash algorithm
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// All configured digests, keyed by algorithm
+    #[serde(default, skip_serializing_if = "Digests::is_empty")]
+    pub hashes: Digests,
+    /// No text encoding fits the first `SNIFF_LEN` bytes (a NUL outside UTF-16)
+    #[serde(default)]
+    pub is_binary: bool,
+    /// Text statistics; `None` for binary files
+    #[serde(default)]
+    pub line_count: Option<u64>,
+    #[serde(default)]
+    pub word_count: Option<u64>,
+    #[serde(default)]
+    pub char_count: Option<u64>,
+    /// Encoding, line endings and whitespace of text files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextInfo>,
+    /// Document structure of Markdown files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markdown: Option<MarkdownInfo>,
+    /// Architecture, linking and hardening flags of ELF binaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elf: Option<ElfInfo>,
+    /// Language and code/comment/blank line counts of source files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sloc: Option<SourceLines>,
+    /// EXIF, PNG text, PDF Info/XMP or Office `docProps` metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<DocumentMetadata>,
+    /// Byte entropy and compressed/encrypted classification; `None` when empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<EntropyProfile>,
+    /// Listing summary when this file is an archive that was opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveInfo>,
+    /// Set on virtual files listed from inside an archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_entry: Option<ArchiveEntry>,
+}
+
+impl FileInfo {
+    pub(crate) fn new(path: String, size: u64) -> Self {
+        Self {
+            path,
+            size,
+            file_type: "unknown".to_string(),
+            content_type: ContentType::default(),
+            modified: None,
+            accessed: None,
+            changed: None,
+            created: None,
+            permissions: None,
+            ownership: None,
+            storage: None,
+            xattrs: None,
+            checksum: None,
+            hashes: Digests::new(),
+            is_binary: false,
+            line_count: None,
+            word_count: None,
+            char_count: None,
+            text: None,
+            markdown: None,
+            elf: None,
+            sloc: None,
+            metadata: None,
+            entropy: None,
+            archive: None,
+            archive_entry: None,
+        }
+    }
+}
+
+/// What the engine computes for each file
+#[derive(Debug, Clone)]
+pub struct EngineOptions {
+    /// Files larger than this are rejected without being read
+    pub max_file_size: u64,
+    /// Digests to compute; the first one becomes `checksum`
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Read extended attributes of files on disk
+    pub xattrs: bool,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            hash_algorithms: vec![HashAlgorithm::Sha256],
+            xattrs: false,
+        }
+    }
+}
+
+/// Analysis engine errors
+#[derive(Error, Debug)]
+pub enum EngineError {
+    #[error("File is {size} bytes, over the {limit} byte limit")]
+    TooLarge { size: u64, limit: u64 },
+
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+}
+
+/// Analyze a file or directory on disk
+pub fn analyze_path(path: &Path, options: &EngineOptions) -> Result<FileInfo, EngineError> {
+    let metadata = path.metadata()?;
+    analyze_with_metadata(path, &metadata, options)
+}
+
+/// Analyze a path whose metadata the caller has already read
+pub fn analyze_with_metadata(
+    path: &Path,
+    metadata: &Metadata,
+    options: &EngineOptions,
+) -> Result<FileInfo, EngineError> {
+    let mut info = FileInfo::new(path.display().to_string(), metadata.len());
+    apply_metadata(&mut info, path, metadata, options);
+
+    if metadata.is_dir() {
+        info.file_type = "directory".to_string();
+        return Ok(info);
+    }
+    if !metadata.is_file() {
+        info.file_type = extension_of(&info.path).unwrap_or_else(|| "unknown".to_string());
+        return Ok(info);
+    }
+    if metadata.len() > options.max_file_size {
+        return Err(EngineError::TooLarge {
+            size: metadata.len(),
+            limit: options.max_file_size,
+        });
+    }
+
+    analyze_content(&mut info, File::open(path)?, options)?;
+    Ok(info)
+}
+
+/// Fill the timestamps, permissions, ownership, storage and extended
+/// attributes, which can change without touching the content
+pub fn apply_metadata(info: &mut FileInfo, path: &Path, metadata: &Metadata, options: &EngineOptions) {
+    info.modified = metadata.modified().ok().map(timestamps::rfc3339);
+    info.accessed = metadata.accessed().ok().map(timestamps::rfc3339);
+    info.created = metadata.created().ok().map(timestamps::rfc3339);
+    info.permissions = Some(permissions_of(metadata));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        info.ownership = Some(Ownership::from_metadata(metadata));
+        info.storage = Some(StorageInfo::from_metadata(metadata));
+        info.changed = Some(timestamps::from_unix(metadata.ctime(), metadata.ctime_nsec() as u32));
+    }
+    if options.xattrs {
+        // Unsupported filesystems and denied reads just leave this empty
+        info.xattrs = xattrs::read(path).ok().flatten();
+    }
+}
+
+/// Analyze in-memory content named `name` (WASM uploads, previews)
+pub fn analyze_bytes(data: &[u8], name: &str, options: &EngineOptions) -> FileInfo {
+    let mut info = FileInfo::new(name.to_string(), data.len() as u64);
+    // Reading from a slice cannot fail
+    let _ = analyze_content(&mut info, data, options);
+    info
+}
+
+fn analyze_content<R: Read>(info: &mut FileInfo, mut reader: R, options: &EngineOptions) -> io::Result<()> {
+    let ext = extension_of(&info.path);
+
+    let mut head = Vec::with_capacity(sniff::SNIFF_LEN);
+    (&mut reader).take(sniff::SNIFF_LEN as u64).read_to_end(&mut head)?;
+    let mut content_type = sniff::sniff(&head, ext.as_deref());
+    let detected = encoding::detect(&head);
+    let is_binary = detected.is_none();
+
+    let mut hasher = MultiHasher::new(&options.hash_algorithms);
+    let mut entropy = EntropyCounter::new(info.size);
+    let mut text = detected.map(|d| TextCounter::new(d.encoding, d.bom));
+    // 8-bit text without a BOM is counted both ways until UTF-8 validity is known
+    let mut legacy = detected
+        .filter(|d| d.encoding == TextEncoding::Utf8 && !d.bom)
+        .map(|_| TextCounter::new(TextEncoding::Windows1252, false));
+    // Markdown structure, ELF headers, document metadata and source line
+    // counts need the whole file in memory
+    let is_elf = content_type.kind.as_deref() == Some("elf");
+    let is_markdown = !is_binary && ext.as_deref().is_some_and(markdown::is_markdown_extension);
+    let doc_format = docmeta::format_of(&head, content_type.kind.as_deref(), ext.as_deref());
+    let language = (!is_binary).then(|| sloc::detect(&info.path, ext.as_deref(), &head)).flatten();
+    let whole_limit = if is_elf {
+        ELF_MAX_LEN
+    } else if doc_format.is_some() {
+        DOCUMENT_MAX_LEN
+    } else if language.is_some() {
+        SOURCE_MAX_LEN
+    } else {
+        MARKDOWN_MAX_LEN
+    };
+    let mut whole = (is_elf || is_markdown || doc_format.is_some() || language.is_some()).then(Vec::new);
+    let mut feed = |chunk: &[u8]| {
+        hasher.update(chunk);
+        entropy.update(chunk);
+        if let Some(text) = text.as_mut() {
+            text.update(chunk);
+        }
+        if let Some(legacy) = legacy.as_mut() {
+            legacy.update(chunk);
+        }
+        if let Some(buf) = whole.as_mut() {
+            if buf.len() + chunk.len() > whole_limit {
+                whole = None;
+            } else {
+                buf.extend_from_slice(chunk);
+            }
+        }
+    };
+
+    feed(&head);
+    let mut buf = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        feed(&buf[..n]);
+    }
+
+    info.hashes = hasher.finish();
+    info.checksum = options.hash_algorithms.first()
+        .and_then(|a| info.hashes.get(a).cloned());
+    info.file_type = ext
+        .or_else(|| content_type.kind.as_deref().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+    if detected.is_some_and(|d| matches!(d.encoding, TextEncoding::Utf16Le | TextEncoding::Utf16Be))
+        && content_type.kind.is_none()
+    {
+        content_type.mime_type = "text/plain".to_string();
+        content_type.confidence = 0.5;
+    }
+    info.entropy = entropy.finish(
+        entropy::is_compressed_type(&info.file_type)
+            || content_type.kind.as_deref().is_some_and(entropy::is_compressed_type),
+    );
+    info.content_type = content_type;
+    info.is_binary = is_binary;
+    if is_elf {
+        info.elf = whole.as_deref().and_then(elf::parse);
+    }
+    if let (Some(format), Some(data)) = (doc_format, whole.as_deref()) {
+        info.metadata = docmeta::extract(data, format);
+    }
+
+    let text = text.map(TextCounter::finish);
+    let text = match (text, legacy.map(TextCounter::finish)) {
+        (Some(utf8), Some(legacy)) if utf8.3.invalid_sequences > 0 => Some(legacy),
+        (text, _) => text,
+    };
+    if let Some((lines, words, chars, text_info)) = text {
+        info.line_count = Some(lines);
+        info.word_count = Some(words);
+        info.char_count = Some(chars);
+        if let Some(doc) = whole.as_deref().filter(|_| is_markdown || language.is_some()) {
+            let decoded = encoding::decode(doc, text_info.encoding);
+            if is_markdown {
+                info.markdown = Some(markdown::analyze(&decoded));
+            }
+            info.sloc = language.and_then(|language| {
+                let counts = sloc::count(&decoded, language)?;
+                Some(SourceLines { language: language.to_string(), counts })
+            });
+        }
+        info.text = Some(text_info);
+    }
+    Ok(())
+}
+
+/// Lowercase extension of a path or file name
+pub fn extension_of(path: &str) -> Option<String> {
+    Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+#[cfg(unix)]
+fn permissions_of(metadata: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    format!("{:o}", metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn permissions_of(metadata: &Metadata) -> String {
+    if metadata.permissions().readonly() {
+        "readonly".to_string()
+    } else {
+        "readwrite".to_string()
+    }
+}
+
+/// Streaming line/word/char counts over decoded text, with `str::lines`
+/// and `split_whitespace` semantics; undecodable bytes count as U+FFFD
+struct TextCounter {
+    decoder: Decoder,
+    counts: CharCounts,
+    line_stats: LineStats,
+}
+
+#[derive(Default)]
+struct CharCounts {
+    lines: u64,
+    words: u64,
+    chars: u64,
+    in_word: bool,
+    /// Last character seen, to count a final unterminated line
+    last: Option<char>,
+}
+
+impl CharCounts {
+    fn push(&mut self, c: char) {
+        self.chars += 1;
+        if c == '\n' {
+            self.lines += 1;
+        }
+        if c.is_whitespace() {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            self.words += 1;
+        }
+        self.last = Some(c);
+    }
+}
+
+impl TextCounter {
+    fn new(encoding: TextEncoding, bom: bool) -> Self {
+        Self {
+            decoder: Decoder::new(encoding, bom),
+            counts: CharCounts::default(),
+            line_stats: LineStats::default(),
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        let (counts, line_stats) = (&mut self.counts, &mut self.line_stats);
+        self.decoder.decode(chunk, |c| {
+            counts.push(c);
+            line_stats.push(c);
+        });
+    }
+
+    fn finish(mut self) -> (u64, u64, u64, TextInfo) {
+        let (counts, line_stats) = (&mut self.counts, &mut self.line_stats);
+        self.decoder.finish(|c| {
+            counts.push(c);
+            line_stats.push(c);
+        });
+        if self.counts.last.is_some_and(|c| c != '\n') {
+            self.counts.lines += 1;
+        }
+        let text = self.line_stats.finish(self.decoder.encoding(), self.decoder.bom(), self.decoder.invalid());
+        (self.counts.lines, self.counts.words, self.counts.chars, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(chunks: &[&[u8]]) -> (u64, u64, u64) {
+        let mut counter = TextCounter::new(TextEncoding::Utf8, false);
+        for chunk in chunks {
+            counter.update(chunk);
+        }
+        let (lines, words, chars, _) = counter.finish();
+        (lines, words, chars)
+    }
+
+    #[test]
+    fn test_text_counts_match_str_semantics() {
+        let text = "Hello World\r\nLine two\n\nLine  three";
+        let expected = (
+            text.lines().count() as u64,
+            text.split_whitespace().count() as u64,
+            text.chars().count() as u64,
+        );
+        assert_eq!(counts(&[text.as_bytes()]), expected);
+        assert_eq!(counts(&[b"one\n"]), (1, 1, 4));
+        assert_eq!(counts(&[]), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_text_counts_across_chunk_boundaries() {
+        let text = "héllo wörld\nnext";
+        let bytes = text.as_bytes();
+        // Split inside the two-byte 'é'
+        assert_eq!(counts(&[&bytes[..2], &bytes[2..]]), (2, 3, 16));
+        assert_eq!(counts(&[b"a\xffb"]), (1, 1, 3));
+    }
+
+    #[test]
+    fn test_analyze_bytes() {
+        let text = analyze_bytes(b"Hello World\nLine two", "notes.TXT", &EngineOptions::default());
+        assert_eq!(text.file_type, "txt");
+        assert_eq!(text.line_count, Some(2));
+        assert_eq!(text.word_count, Some(4));
+        assert!(!text.is_binary);
+        assert_eq!(text.checksum.as_deref(), text.hashes.get(&HashAlgorithm::Sha256).map(String::as_str));
+
+        let binary = analyze_bytes(b"\x7fELF\x02\x01\x01\0\0", "tool", &EngineOptions::default());
+        assert_eq!(binary.file_type, "elf");
+        assert!(binary.is_binary);
+        assert_eq!(binary.line_count, None);
+        assert!(analyze_bytes(b"", "empty.txt", &EngineOptions::default()).entropy.is_none());
+    }
+
+    #[test]
+    fn test_decoded_text_counts() {
+        let options = EngineOptions::default();
+        let mut utf16: Vec<u8> = b"\xFF\xFE".to_vec();
+        utf16.extend("héllo wörld\r\nzwei \r\n".encode_utf16().flat_map(u16::to_le_bytes));
+        let info = analyze_bytes(&utf16, "notes.txt", &options);
+        let text = info.text.as_ref().unwrap();
+        assert!(!info.is_binary);
+        assert_eq!(info.content_type.mime_type, "text/plain");
+        assert_eq!((text.encoding, text.bom), (TextEncoding::Utf16Le, true));
+        assert_eq!((info.line_count, info.word_count, info.char_count), (Some(2), Some(3), Some(20)));
+        assert_eq!(text.line_ending, encoding::LineEnding::Crlf);
+        assert_eq!(text.trailing_whitespace_lines, 1);
+        assert!(text.final_newline);
+
+        let latin1 = analyze_bytes(b"caf\xe9 cr\xe8me\nna\xefve", "menu.txt", &options);
+        let text = latin1.text.as_ref().unwrap();
+        assert_eq!(text.encoding, TextEncoding::Windows1252);
+        assert_eq!(text.invalid_sequences, 0);
+        assert_eq!(latin1.char_count, Some(16));
+        assert!(!text.final_newline);
+
+        let ascii = analyze_bytes(b"plain\n", "a.txt", &options);
+        assert_eq!(ascii.text.unwrap().encoding, TextEncoding::Ascii);
+        let utf8 = analyze_bytes("\u{FEFF}naïve\n".as_bytes(), "b.txt", &options);
+        assert_eq!(utf8.text.as_ref().map(|t| (t.encoding, t.bom)), Some((TextEncoding::Utf8, true)));
+        assert_eq!(utf8.char_count, Some(6));
+    }
+
+    #[test]
+    fn test_analyze_path_matches_bytes() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("data.md");
+        let content = "# Title\n\nSome *markdown* text.\n".repeat(5000);
+        std::fs::write(&path, &content).unwrap();
+
+        let options = EngineOptions::default();
+        let from_disk = analyze_path(&path, &options).unwrap();
+        let in_memory = analyze_bytes(content.as_bytes(), &path.display().to_string(), &options);
+
+        assert_eq!(from_disk.line_count, in_memory.line_count);
+        assert_eq!(from_disk.word_count, in_memory.word_count);
+        assert_eq!(from_disk.checksum, in_memory.checksum);
+        assert_eq!(from_disk.entropy, in_memory.entropy);
+        assert!(from_disk.permissions.is_some());
+
+        // RFC 3339 times from the file's own metadata
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(from_disk.modified, Some(crate::timestamps::rfc3339(mtime)));
+        assert!(from_disk.modified.as_deref().unwrap().ends_with('Z'));
+        assert!(from_disk.accessed.is_some());
+        #[cfg(unix)]
+        assert!(from_disk.changed.as_deref().and_then(crate::timestamps::parse_unix).is_some());
+    }
+
+    #[test]
+    fn test_size_limit_and_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("big.bin");
+        std::fs::write(&path, [0u8; 64]).unwrap();
+
+        let options = EngineOptions { max_file_size: 10, ..Default::default() };
+        assert!(matches!(
+            analyze_path(&path, &options),
+            Err(EngineError::TooLarge { size: 64, limit: 10 })
+        ));
+        assert_eq!(analyze_path(temp.path(), &options).unwrap().file_type, "directory");
+    }
+}
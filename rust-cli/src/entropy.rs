@@ -0,0 +1,257 @@
+//! Entropy Profiling Module
+//!
+//! Shannon entropy of a file's bytes, overall and per window, and a
+//! classification that separates ordinary content from data that is likely
+//! compressed or encrypted. A file whose bytes are uniformly random but whose
+//! format does not explain it (no archive or media signature) is the signature
+//! of ransomware output or a hidden encrypted container.
+
+use serde::{Deserialize, Serialize};
+
+/// Smallest window in the profile
+const MIN_WINDOW: u64 = 4096;
+
+/// Windows per profile; larger files get wider windows
+const MAX_WINDOWS: u64 = 64;
+
+/// Files shorter than this are never classified as high entropy
+const MIN_CLASSIFY_LEN: u64 = 1024;
+
+/// Bits per byte from which content counts as high entropy
+pub const HIGH_ENTROPY: f64 = 7.2;
+
+/// Overall entropy below this is `low` (text, sparse or structured data)
+const LOW_ENTROPY: f64 = 5.0;
+
+/// Share of windows that must be high entropy for a compressed/encrypted class
+const HIGH_WINDOW_SHARE: f64 = 0.9;
+
+/// Chi-square (255 degrees of freedom) under which bytes look uniformly random
+const UNIFORM_CHI_SQUARE: f64 = 400.0;
+
+/// Formats whose payload is expected to be high entropy
+const COMPRESSED_TYPES: &[&str] = &[
+    "zip", "jar", "docx", "xlsx", "pptx", "odt", "ods", "odp", "apk", "epub",
+    "gz", "tgz", "bz2", "xz", "zst", "lz4", "7z", "rar", "cab",
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "heic",
+    "mp3", "mp4", "m4a", "mkv", "webm", "ogg", "flac", "mov", "avi",
+    "pdf", "woff", "woff2",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntropyClass {
+    /// Text, sparse or highly structured data
+    Low,
+    /// Ordinary binaries and mixed content
+    Moderate,
+    /// High entropy explained by the format, or not uniformly random
+    Compressed,
+    /// Uniformly random bytes in a format that does not explain them
+    Encrypted,
+}
+
+/// Byte entropy of a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntropyProfile {
+    /// Bits per byte over the whole file (0-8)
+    pub overall: f64,
+    /// Bytes per profile window
+    pub window_size: u64,
+    /// Entropy of each window, in file order
+    pub windows: Vec<f64>,
+    /// Fraction of windows at or above [`HIGH_ENTROPY`]
+    pub high_ratio: f64,
+    /// Byte-frequency chi-square against a uniform distribution
+    pub chi_square: f64,
+    pub class: EntropyClass,
+}
+
+impl EntropyProfile {
+    /// Likely compressed or encrypted content
+    pub fn is_high_entropy(&self) -> bool {
+        matches!(self.class, EntropyClass::Compressed | EntropyClass::Encrypted)
+    }
+}
+
+pub fn is_compressed_type(file_type: &str) -> bool {
+    COMPRESSED_TYPES.contains(&file_type)
+}
+
+/// Streaming entropy over a file of `expected_len` bytes
+pub struct EntropyCounter {
+    total: [u64; 256],
+    len: u64,
+    window: [u64; 256],
+    window_len: u64,
+    window_size: u64,
+    windows: Vec<f64>,
+}
+
+impl EntropyCounter {
+    pub fn new(expected_len: u64) -> Self {
+        Self {
+            total: [0; 256],
+            len: 0,
+            window: [0; 256],
+            window_len: 0,
+            window_size: expected_len.div_ceil(MAX_WINDOWS).max(MIN_WINDOW),
+            windows: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut chunk: &[u8]) {
+        while !chunk.is_empty() {
+            let take = ((self.window_size - self.window_len) as usize).min(chunk.len());
+            for &b in &chunk[..take] {
+                self.total[b as usize] += 1;
+                self.window[b as usize] += 1;
+            }
+            self.len += take as u64;
+            self.window_len += take as u64;
+            chunk = &chunk[take..];
+
+            if self.window_len == self.window_size {
+                self.close_window();
+            }
+        }
+    }
+
+    fn close_window(&mut self) {
+        self.windows.push(round(shannon(&self.window, self.window_len)));
+        self.window = [0; 256];
+        self.window_len = 0;
+    }
+
+    /// `None` for empty input; `known_compressed` marks formats expected to be dense
+    pub fn finish(mut self, known_compressed: bool) -> Option<EntropyProfile> {
+        if self.len == 0 {
+            return None;
+        }
+        // A short tail is noise unless it is the only window
+        if self.window_len > 0 && (self.windows.is_empty() || self.window_len >= MIN_WINDOW / 4) {
+            self.close_window();
+        }
+
+        let overall = shannon(&self.total, self.len);
+        let high = self.windows.iter().filter(|&&e| e >= HIGH_ENTROPY).count();
+        let high_ratio = high as f64 / self.windows.len() as f64;
+        let chi_square = chi_square(&self.total, self.len);
+
+        let class = if overall < LOW_ENTROPY {
+            EntropyClass::Low
+        } else if self.len < MIN_CLASSIFY_LEN || overall < HIGH_ENTROPY || high_ratio < HIGH_WINDOW_SHARE {
+            EntropyClass::Moderate
+        } else if known_compressed || chi_square > UNIFORM_CHI_SQUARE {
+            EntropyClass::Compressed
+        } else {
+            EntropyClass::Encrypted
+        };
+
+        Some(EntropyProfile {
+            overall: round(overall),
+            window_size: self.window_size,
+            windows: self.windows,
+            high_ratio: round(high_ratio),
+            chi_square: round(chi_square),
+            class,
+        })
+    }
+}
+
+/// Shannon entropy in bits per byte
+fn shannon(counts: &[u64; 256], len: u64) -> f64 {
+    if len == 0 {
+        return 0.0;
+    }
+    counts.iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / len as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn chi_square(counts: &[u64; 256], len: u64) -> f64 {
+    let expected = len as f64 / 256.0;
+    counts.iter()
+        .map(|&n| {
+            let d = n as f64 - expected;
+            d * d / expected
+        })
+        .sum()
+}
+
+fn round(x: f64) -> f64 {
+    (x * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(data: &[u8], known_compressed: bool) -> EntropyProfile {
+        let mut counter = EntropyCounter::new(data.len() as u64);
+        // Odd chunk size to cross window boundaries
+        for chunk in data.chunks(1000) {
+            counter.update(chunk);
+        }
+        counter.finish(known_compressed).unwrap()
+    }
+
+    /// Deterministic uniformly distributed bytes (xorshift)
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classification() {
+        let text = "The quick brown fox jumps over the lazy dog.\n".repeat(500);
+        let text = profile(text.as_bytes(), false);
+        assert_eq!(text.class, EntropyClass::Low);
+        assert_eq!(text.overall, 4.542);
+
+        let random = random_bytes(256 * 1024);
+        let encrypted = profile(&random, false);
+        assert_eq!(encrypted.class, EntropyClass::Encrypted);
+        assert!(encrypted.overall > 7.99);
+        assert_eq!(encrypted.high_ratio, 1.0);
+        assert!(encrypted.is_high_entropy());
+
+        assert_eq!(profile(&random, true).class, EntropyClass::Compressed);
+        assert_eq!(profile(&random[..512], false).class, EntropyClass::Moderate);
+    }
+
+    #[test]
+    fn test_windowed_profile() {
+        // Zero padding followed by random data: a hidden container
+        let mut data = vec![0u8; 64 * 1024];
+        data.extend(random_bytes(192 * 1024));
+        let p = profile(&data, false);
+
+        assert_eq!(p.window_size, MIN_WINDOW);
+        assert_eq!(p.windows.len(), 64);
+        assert_eq!(p.windows[0], 0.0);
+        assert!(p.windows[63] > 7.9);
+        assert_eq!(p.high_ratio, 0.75);
+        assert_eq!(p.class, EntropyClass::Moderate);
+    }
+
+    #[test]
+    fn test_window_count_is_bounded() {
+        let data = vec![7u8; 10 * 1024 * 1024 + 5];
+        let p = profile(&data, false);
+        assert!(p.windows.len() <= MAX_WINDOWS as usize + 1);
+        assert_eq!(p.overall, 0.0);
+        assert!(EntropyCounter::new(0).finish(false).is_none());
+    }
+}
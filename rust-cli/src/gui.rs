@@ -14,6 +14,7 @@ use std::path::PathBuf;
 use crate::config::Config;
 use crate::analyzer;
 use crate::ro_lock::ReadOnlyLock;
+use crate::secrets;
 
 /// Run the GUI application
 pub async fn run(config: Config) -> Result<()> {
@@ -325,19 +326,23 @@ fn list_directory(path: &PathBuf) -> Vec<PathBuf> {
     entries
 }
 
-async fn run_analysis(source: String, dest: String, config: Config) -> Result<String> {
-    // Acquire read-only lock
-    let _lock = ReadOnlyLock::acquire(&source).await?;
+async fn run_analysis(source: String, dest: String, mut config: Config) -> Result<String> {
+    // Acquire read-only lock; escaping symlinks are not followed under it
+    let lock = ReadOnlyLock::acquire(&source).await?;
+    config.analysis.ro_locked = true;
 
     // Run analysis
-    let results = analyzer::analyze(&source, &config).await?;
+    let mut results = analyzer::analyze(&source, &config).await?;
+    if config.secrets.enabled {
+        tokio::task::block_in_place(|| secrets::scan_results(&lock, &mut results, &config.secrets))?;
+    }
 
     // Export
     analyzer::export(&results, &dest).await?;
 
     Ok(format!(
-        "Analyzed {} files ({} bytes)\nExported to: {}",
-        results.total_files, results.total_size, dest
+        "Analyzed {} files ({} bytes, {} possible secrets)\nExported to: {}",
+        results.total_files, results.total_size, results.secrets.len(), dest
     ))
 }
 
@@ -0,0 +1,173 @@
+//! Content Hashing Module
+//!
+//! Streaming file digests: SHA-256 and SHA-512 via `ring`, BLAKE3 behind the
+//! `blake3` feature. Files are read in fixed-size chunks so memory stays flat
+//! regardless of file size.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use ring::digest;
+use serde::{Deserialize, Serialize};
+
+/// Read buffer size for streaming digests
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Supported digest algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha512,
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+/// Digests keyed by algorithm, hex encoded
+pub type Digests = BTreeMap<HashAlgorithm, String>;
+
+/// Computes several digests over a single pass of the input
+pub struct MultiHasher {
+    ring: Vec<(HashAlgorithm, digest::Context)>,
+    #[cfg(feature = "blake3")]
+    blake3: Option<Box<blake3::Hasher>>,
+}
+
+impl MultiHasher {
+    pub fn new(algorithms: &[HashAlgorithm]) -> Self {
+        let mut hasher = Self {
+            ring: Vec::new(),
+            #[cfg(feature = "blake3")]
+            blake3: None,
+        };
+
+        for &algorithm in algorithms {
+            match algorithm {
+                HashAlgorithm::Sha256 | HashAlgorithm::Sha512
+                    if hasher.ring.iter().any(|(a, _)| *a == algorithm) => {}
+                HashAlgorithm::Sha256 => {
+                    hasher.ring.push((algorithm, digest::Context::new(&digest::SHA256)));
+                }
+                HashAlgorithm::Sha512 => {
+                    hasher.ring.push((algorithm, digest::Context::new(&digest::SHA512)));
+                }
+                #[cfg(feature = "blake3")]
+                HashAlgorithm::Blake3 => {
+                    hasher.blake3.get_or_insert_with(|| Box::new(blake3::Hasher::new()));
+                }
+            }
+        }
+
+        hasher
+    }
+
+    pub fn is_empty(&self) -> bool {
+        #[cfg(feature = "blake3")]
+        if self.blake3.is_some() {
+            return false;
+        }
+        self.ring.is_empty()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for (_, ctx) in &mut self.ring {
+            ctx.update(data);
+        }
+        #[cfg(feature = "blake3")]
+        if let Some(hasher) = &mut self.blake3 {
+            hasher.update(data);
+        }
+    }
+
+    pub fn finish(self) -> Digests {
+        #[allow(unused_mut)]
+        let mut digests: Digests = self.ring
+            .into_iter()
+            .map(|(algorithm, ctx)| (algorithm, to_hex(ctx.finish().as_ref())))
+            .collect();
+        #[cfg(feature = "blake3")]
+        if let Some(hasher) = self.blake3 {
+            digests.insert(HashAlgorithm::Blake3, hasher.finalize().to_hex().to_string());
+        }
+        digests
+    }
+}
+
+/// Stream a reader through the requested digests
+pub fn hash_reader<R: Read>(mut reader: R, algorithms: &[HashAlgorithm]) -> io::Result<Digests> {
+    let mut hasher = MultiHasher::new(algorithms);
+    if hasher.is_empty() {
+        return Ok(Digests::new());
+    }
+
+    let mut buf = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finish())
+}
+
+/// Lowercase hex encoding
+pub fn to_hex(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        out.push(HEX[(b >> 4) as usize] as char);
+        out.push(HEX[(b & 0x0f) as usize] as char);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_known_vector() {
+        let digests = hash_reader(&b"abc"[..], &[HashAlgorithm::Sha256]).unwrap();
+        assert_eq!(
+            digests[&HashAlgorithm::Sha256],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_multiple_algorithms_single_pass() {
+        let digests = hash_reader(
+            &b""[..],
+            &[HashAlgorithm::Sha512, HashAlgorithm::Sha256, HashAlgorithm::Sha256],
+        )
+        .unwrap();
+
+        assert_eq!(digests.len(), 2);
+        assert!(digests[&HashAlgorithm::Sha512].starts_with("cf83e1357eefb8bd"));
+        assert!(digests[&HashAlgorithm::Sha256].starts_with("e3b0c44298fc1c14"));
+    }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let data = vec![0x5au8; HASH_BUFFER_SIZE * 3 + 17];
+        let streamed = hash_reader(&data[..], &[HashAlgorithm::Sha256]).unwrap();
+        let one_shot = to_hex(digest::digest(&digest::SHA256, &data).as_ref());
+        assert_eq!(streamed[&HashAlgorithm::Sha256], one_shot);
+    }
+
+    #[test]
+    fn test_no_algorithms() {
+        let digests = hash_reader(&b"abc"[..], &[]).unwrap();
+        assert!(digests.is_empty());
+    }
+
+    #[test]
+    fn test_algorithm_config_names() {
+        let algos: Vec<HashAlgorithm> = serde_json::from_str(r#"["sha256", "sha512"]"#).unwrap();
+        assert_eq!(algos, vec![HashAlgorithm::Sha256, HashAlgorithm::Sha512]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Ignore Rules Module
+//!
+//! Gitignore-style path matching for the directory walker. Combines the
+//! `include_patterns`/`exclude_patterns` from `diamond.toml` with any
+//! `.gitignore` and `.diamondignore` files found while descending, so that
+//! excluded directories are pruned before they are ever read.
+
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::config::AnalysisConfig;
+
+/// Per-directory ignore files, in increasing precedence
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".diamondignore"];
+
+/// A rule that was in effect during a walk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IgnoreRule {
+    /// `include_patterns`, `exclude_patterns`, or the ignore file path
+    pub source: String,
+    pub pattern: String,
+}
+
+/// Stateful matcher fed with entries in depth-first walk order
+pub struct IgnoreMatcher {
+    include: Option<Gitignore>,
+    exclude: Gitignore,
+    respect_ignore_files: bool,
+    /// Ignore files of the directories on the current walk path, innermost last
+    stack: Vec<(PathBuf, Option<Gitignore>)>,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreMatcher {
+    /// Build a matcher rooted at the analysis source
+    pub fn new(root: &Path, config: &AnalysisConfig) -> Self {
+        let mut rules = Vec::new();
+
+        let include = if config.include_patterns.iter().all(|p| p.trim() == "*") {
+            None
+        } else {
+            Some(build_patterns(root, "include_patterns", &config.include_patterns, &mut rules))
+        };
+        let exclude = build_patterns(root, "exclude_patterns", &config.exclude_patterns, &mut rules);
+
+        Self {
+            include,
+            exclude,
+            respect_ignore_files: config.respect_ignore_files,
+            stack: Vec::new(),
+            rules,
+        }
+    }
+
+    /// Decide whether a walk entry should be skipped.
+    ///
+    /// Entries must arrive in depth-first order (as WalkDir yields them) so the
+    /// per-directory ignore file stack stays in sync. The root is never excluded.
+    pub fn is_excluded(&mut self, path: &Path, is_dir: bool, depth: usize) -> bool {
+        if depth == 0 {
+            return false;
+        }
+
+        let config_match = self.exclude.matched(path, is_dir);
+        if config_match.is_ignore() {
+            return true;
+        }
+
+        if self.respect_ignore_files && !config_match.is_whitelist() {
+            if let Some(parent) = path.parent() {
+                self.enter_dir(parent);
+            }
+            for (_, gitignore) in self.stack.iter().rev() {
+                if let Some(gitignore) = gitignore {
+                    let m = gitignore.matched(path, is_dir);
+                    if m.is_ignore() {
+                        return true;
+                    }
+                    if m.is_whitelist() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !is_dir {
+            if let Some(include) = &self.include {
+                return !include.matched(path, false).is_ignore();
+            }
+        }
+
+        false
+    }
+
+    /// Rules that were active during the walk, in load order
+    pub fn into_rules(self) -> Vec<IgnoreRule> {
+        self.rules
+    }
+
+    /// Sync the stack with `dir`, loading its ignore files on first visit
+    fn enter_dir(&mut self, dir: &Path) {
+        while let Some((top, _)) = self.stack.last() {
+            if dir.starts_with(top) {
+                break;
+            }
+            self.stack.pop();
+        }
+
+        if self.stack.last().is_some_and(|(top, _)| top == dir) {
+            return;
+        }
+
+        let gitignore = self.load_ignore_files(dir);
+        self.stack.push((dir.to_path_buf(), gitignore));
+    }
+
+    fn load_ignore_files(&mut self, dir: &Path) -> Option<Gitignore> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+
+        for name in IGNORE_FILES {
+            let file = dir.join(name);
+            let content = match std::fs::read_to_string(&file) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+            debug!("Loading ignore rules from {}", file.display());
+            found = true;
+
+            for line in content.lines() {
+                let pattern = line.trim_end();
+                if pattern.is_empty() || pattern.starts_with('#') {
+                    continue;
+                }
+                match builder.add_line(Some(file.clone()), pattern) {
+                    Ok(_) => self.rules.push(IgnoreRule {
+                        source: file.display().to_string(),
+                        pattern: pattern.to_string(),
+                    }),
+                    Err(e) => warn!("Invalid pattern in {}: {}", file.display(), e),
+                }
+            }
+        }
+
+        if !found {
+            return None;
+        }
+        builder.build().map_err(|e| warn!("Ignoring rules in {}: {}", dir.display(), e)).ok()
+    }
+}
+
+fn build_patterns(root: &Path, source: &str, patterns: &[String], rules: &mut Vec<IgnoreRule>) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+
+    for pattern in patterns {
+        match builder.add_line(None, pattern) {
+            Ok(_) => rules.push(IgnoreRule {
+                source: source.to_string(),
+                pattern: pattern.clone(),
+            }),
+            Err(e) => warn!("Invalid {} entry '{}': {}", source, pattern, e),
+        }
+    }
+
+    builder.build().unwrap_or_else(|e| {
+        warn!("Ignoring {}: {}", source, e);
+        Gitignore::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+    use walkdir::WalkDir;
+
+    fn walk(root: &Path, config: &AnalysisConfig) -> (Vec<String>, Vec<IgnoreRule>) {
+        let mut matcher = IgnoreMatcher::new(root, config);
+        let mut files: Vec<String> = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !matcher.is_excluded(e.path(), e.file_type().is_dir(), e.depth()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().strip_prefix(root).unwrap().display().to_string())
+            .collect();
+        files.sort();
+        (files, matcher.into_rules())
+    }
+
+    #[test]
+    fn test_config_excludes_prune_directories() {
+        let temp = tempdir().unwrap();
+        fs::create_dir_all(temp.path().join("node_modules/pkg")).unwrap();
+        fs::write(temp.path().join("node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(temp.path().join("keep.txt"), "x").unwrap();
+        fs::write(temp.path().join("drop.tmp"), "x").unwrap();
+
+        let config = AnalysisConfig {
+            exclude_patterns: vec!["node_modules".to_string(), "*.tmp".to_string()],
+            ..Default::default()
+        };
+        let (files, rules) = walk(temp.path(), &config);
+
+        assert_eq!(files, vec!["keep.txt"]);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].source, "exclude_patterns");
+    }
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let temp = tempdir().unwrap();
+        fs::create_dir_all(temp.path().join("sub/target")).unwrap();
+        fs::write(temp.path().join(".gitignore"), "*.log\n# comment\n").unwrap();
+        fs::write(temp.path().join("sub/.diamondignore"), "target/\n!keep.log\n").unwrap();
+        fs::write(temp.path().join("a.log"), "x").unwrap();
+        fs::write(temp.path().join("sub/keep.log"), "x").unwrap();
+        fs::write(temp.path().join("sub/other.log"), "x").unwrap();
+        fs::write(temp.path().join("sub/target/out.bin"), "x").unwrap();
+
+        let (files, rules) = walk(temp.path(), &AnalysisConfig::default());
+
+        assert_eq!(files, vec![".gitignore", "sub/.diamondignore", "sub/keep.log"]);
+        assert!(rules.iter().any(|r| r.pattern == "target/" && r.source.ends_with(".diamondignore")));
+        assert!(!rules.iter().any(|r| r.pattern.starts_with('#')));
+    }
+
+    #[test]
+    fn test_ignore_files_can_be_disabled() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::write(temp.path().join("a.log"), "x").unwrap();
+
+        let config = AnalysisConfig {
+            respect_ignore_files: false,
+            ..Default::default()
+        };
+        let (files, _) = walk(temp.path(), &config);
+
+        assert_eq!(files, vec![".gitignore", "a.log"]);
+    }
+
+    #[test]
+    fn test_include_patterns_filter_files_only() {
+        let temp = tempdir().unwrap();
+        fs::create_dir_all(temp.path().join("src")).unwrap();
+        fs::write(temp.path().join("src/main.rs"), "x").unwrap();
+        fs::write(temp.path().join("README.md"), "x").unwrap();
+
+        let config = AnalysisConfig {
+            include_patterns: vec!["*.rs".to_string()],
+            ..Default::default()
+        };
+        let (files, _) = walk(temp.path(), &config);
+
+        assert_eq!(files, vec!["src/main.rs"]);
+    }
+}
@@ -9,6 +9,9 @@ use tracing::debug;
 
 use crate::analyzer;
 use crate::config::Config;
+use crate::scan_errors::{IssueReason, ScanIssue, ScanIssues};
+use diamond_drill::engine::{self, EngineError, FileInfo};
+use diamond_drill::markdown;
 
 /// Incoming message from host
 #[derive(Debug, Deserialize)]
@@ -50,26 +53,17 @@ impl PluginResponse {
     }
 }
 
-/// Analysis result for file
-#[derive(Debug, Serialize)]
-pub struct FileAnalysis {
-    pub path: String,
-    pub size: u64,
-    pub file_type: String,
-    pub permissions: String,
-    pub hash: Option<String>,
-    pub line_count: Option<u64>,
-    pub word_count: Option<u64>,
-    pub is_binary: bool,
-}
-
 /// Report result
 #[derive(Debug, Serialize)]
 pub struct Report {
     pub generated_at: String,
     pub files_analyzed: usize,
     pub total_size: u64,
-    pub analyses: Vec<FileAnalysis>,
+    pub analyses: Vec<FileInfo>,
+    /// Paths that could not be read
+    pub errors: Vec<ScanIssue>,
+    /// Paths left out on purpose (too large)
+    pub skipped: Vec<ScanIssue>,
 }
 
 /// Run the plugin server (IPC mode)
@@ -159,85 +153,45 @@ async fn handle_analyze(id: String, payload: serde_json::Value) -> PluginRespons
 
     let mut analyses = Vec::new();
     let config = Config::default();
+    let mut issues = ScanIssues::default();
 
+    // Files that cannot be analyzed are listed, not fatal to the request
     for path in &files {
         match analyze_file(path, &config).await {
             Ok(analysis) => analyses.push(analysis),
-            Err(e) => {
-                return PluginResponse::error(
-                    id,
-                    format!("Failed to analyze {}: {}", path, e),
-                );
-            }
+            Err(e) => issues.push(issue_of(path, &e)),
         }
     }
 
+    let broken_links = markdown::check_links(&analyses);
+
     PluginResponse::success(
         id,
         serde_json::json!({
             "files_analyzed": analyses.len(),
-            "analyses": analyses
+            "analyses": analyses,
+            "broken_links": broken_links,
+            "errors": issues.errors,
+            "skipped": issues.skipped
         }),
     )
 }
 
-async fn analyze_file(path: &str, _config: &Config) -> Result<FileAnalysis> {
-    let metadata = tokio::fs::metadata(path).await?;
-    let file_type = if metadata.is_dir() {
-        "directory".to_string()
-    } else if metadata.is_symlink() {
-        "symlink".to_string()
-    } else {
-        // Try to detect by extension
-        std::path::Path::new(path)
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| e.to_lowercase())
-            .unwrap_or_else(|| "unknown".to_string())
-    };
-
-    #[cfg(unix)]
-    let permissions = {
-        use std::os::unix::fs::PermissionsExt;
-        format!("{:o}", metadata.permissions().mode() & 0o777)
-    };
-    #[cfg(not(unix))]
-    let permissions = if metadata.permissions().readonly() {
-        "readonly".to_string()
-    } else {
-        "readwrite".to_string()
-    };
-
-    // Read file for text analysis if not too large
-    let (line_count, word_count, is_binary) = if metadata.is_file() && metadata.len() < 10_000_000 {
-        match tokio::fs::read(path).await {
-            Ok(content) => {
-                let is_binary = content.iter().take(8192).any(|&b| b == 0);
-                if is_binary {
-                    (None, None, true)
-                } else {
-                    let text = String::from_utf8_lossy(&content);
-                    let lines = text.lines().count() as u64;
-                    let words = text.split_whitespace().count() as u64;
-                    (Some(lines), Some(words), false)
-                }
-            }
-            Err(_) => (None, None, false),
-        }
-    } else {
-        (None, None, false)
-    };
+/// Analyze one path with the shared engine
+async fn analyze_file(path: &str, config: &Config) -> Result<FileInfo> {
+    let options = config.analysis.engine_options();
+    let path = std::path::PathBuf::from(path);
+    let info = tokio::task::spawn_blocking(move || engine::analyze_path(&path, &options)).await??;
+    Ok(info)
+}
 
-    Ok(FileAnalysis {
-        path: path.to_string(),
-        size: metadata.len(),
-        file_type,
-        permissions,
-        hash: None, // Could add SHA256 here
-        line_count,
-        word_count,
-        is_binary,
-    })
+/// Why `path` could not be analyzed, classified like the walker's issues
+fn issue_of(path: &str, error: &anyhow::Error) -> ScanIssue {
+    let path = std::path::Path::new(path);
+    match error.downcast_ref::<EngineError>() {
+        Some(engine_error) => ScanIssue::from_engine(path, engine_error),
+        None => ScanIssue::new(path, IssueReason::Io, error.to_string()),
+    }
 }
 
 async fn handle_report(id: String, payload: serde_json::Value) -> PluginResponse {
@@ -249,6 +203,7 @@ async fn handle_report(id: String, payload: serde_json::Value) -> PluginResponse
     let config = Config::default();
     let mut analyses = Vec::new();
     let mut total_size = 0u64;
+    let mut issues = ScanIssues::default();
 
     for path in &files {
         match analyze_file(path, &config).await {
@@ -256,9 +211,7 @@ async fn handle_report(id: String, payload: serde_json::Value) -> PluginResponse
                 total_size += analysis.size;
                 analyses.push(analysis);
             }
-            Err(e) => {
-                debug!("Skipping {}: {}", path, e);
-            }
+            Err(e) => issues.push(issue_of(path, &e)),
         }
     }
 
@@ -267,6 +220,8 @@ async fn handle_report(id: String, payload: serde_json::Value) -> PluginResponse
         files_analyzed: analyses.len(),
         total_size,
         analyses,
+        errors: issues.errors,
+        skipped: issues.skipped,
     };
 
     PluginResponse::success(id, serde_json::to_value(report).unwrap())
@@ -317,7 +272,23 @@ async fn handle_deep_analyze(id: String, payload: serde_json::Value) -> PluginRe
         None => return PluginResponse::error(id, "Missing 'path' in payload".to_string()),
     };
 
-    let config = Config::default();
+    // Same walker and ignore matcher as the CLI; the host may override patterns
+    let mut config = Config::default();
+    if let Some(patterns) = payload.get("include_patterns") {
+        config.analysis.include_patterns = match serde_json::from_value(patterns.clone()) {
+            Ok(p) => p,
+            Err(e) => return PluginResponse::error(id, format!("Invalid 'include_patterns': {}", e)),
+        };
+    }
+    if let Some(patterns) = payload.get("exclude_patterns") {
+        config.analysis.exclude_patterns = match serde_json::from_value(patterns.clone()) {
+            Ok(p) => p,
+            Err(e) => return PluginResponse::error(id, format!("Invalid 'exclude_patterns': {}", e)),
+        };
+    }
+    if let Some(respect) = payload.get("respect_ignore_files").and_then(|r| r.as_bool()) {
+        config.analysis.respect_ignore_files = respect;
+    }
 
     match analyzer::analyze(path, &config).await {
         Ok(results) => {
@@ -430,6 +401,159 @@ mod tests {
         assert!(resp.success);
     }
 
+    #[tokio::test]
+    async fn test_analyze_file_hash() {
+        let temp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(temp.path(), "abc").unwrap();
+
+        let analysis = analyze_file(temp.path().to_str().unwrap(), &Config::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            analysis.checksum.as_deref(),
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(analysis.line_count, Some(1));
+        assert_eq!(analysis.permissions.as_deref().map(str::len), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_file_sniffs_mime() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("image.png");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let analysis = analyze_file(path.to_str().unwrap(), &Config::default()).await.unwrap();
+        assert_eq!(analysis.content_type.mime_type, "image/png");
+        assert!(!analysis.content_type.extension_mismatch);
+        assert!(analysis.is_binary);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_markdown() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("notes.md");
+        std::fs::write(&path, "# Notes\n\n- [x] done\n- [ ] todo\n\n[next](next.md)\n").unwrap();
+
+        let resp = handle_analyze(
+            "md-1".to_string(),
+            serde_json::json!({ "files": [path.to_str().unwrap()] }),
+        )
+        .await;
+
+        let data = resp.data.unwrap();
+        let markdown = &data["analyses"][0]["markdown"];
+        assert_eq!(markdown["headings"][0]["anchor"], "notes");
+        assert_eq!(markdown["tasks_done"], 1);
+        assert_eq!(data["broken_links"][0]["target"], "next.md");
+    }
+
+    #[tokio::test]
+    async fn test_analyze_document_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("scan.png");
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x20\0\0\0\x10\x08\x06\0\0\0\0\0\0\0".to_vec();
+        png.extend_from_slice(b"\0\0\0\x0ctEXtAuthor\0alice\0\0\0\0");
+        std::fs::write(&path, png).unwrap();
+
+        let resp = handle_analyze(
+            "meta-1".to_string(),
+            serde_json::json!({ "files": [path.to_str().unwrap()] }),
+        )
+        .await;
+
+        let metadata = &resp.data.unwrap()["analyses"][0]["metadata"];
+        assert_eq!(metadata["format"], "png");
+        assert_eq!(metadata["width"], 32);
+        assert_eq!(metadata["author"], "alice");
+    }
+
+    #[tokio::test]
+    async fn test_deep_analyze_exclude_override() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp.path().join("node_modules")).unwrap();
+        std::fs::write(temp.path().join("node_modules/dep.js"), "x").unwrap();
+        std::fs::write(temp.path().join("index.md"), "# Hi").unwrap();
+
+        let resp = handle_deep_analyze(
+            "deep-1".to_string(),
+            serde_json::json!({
+                "path": temp.path().to_str().unwrap(),
+                "exclude_patterns": ["node_modules/"]
+            }),
+        )
+        .await;
+
+        let data = resp.data.unwrap();
+        assert_eq!(data["total_files"], 1);
+        assert_eq!(data["ignore_rules"][0]["pattern"], "node_modules/");
+    }
+
+    #[tokio::test]
+    async fn test_deep_analyze_rejects_malformed_patterns() {
+        let temp = tempfile::tempdir().unwrap();
+
+        let resp = handle_deep_analyze(
+            "deep-2".to_string(),
+            serde_json::json!({
+                "path": temp.path().to_str().unwrap(),
+                "exclude_patterns": "node_modules/"
+            }),
+        )
+        .await;
+
+        assert!(!resp.success);
+        assert!(resp.error.unwrap().contains("exclude_patterns"));
+    }
+
+    #[tokio::test]
+    async fn test_report_lists_errors() {
+        let temp = tempfile::tempdir().unwrap();
+        let present = temp.path().join("here.md");
+        std::fs::write(&present, "# Here").unwrap();
+        let missing = temp.path().join("gone.md");
+
+        let resp = handle_report(
+            "report-1".to_string(),
+            serde_json::json!({ "files": [present.to_str().unwrap(), missing.to_str().unwrap()] }),
+        )
+        .await;
+
+        let data = resp.data.unwrap();
+        assert_eq!(data["files_analyzed"], 1);
+        assert_eq!(data["errors"][0]["path"], missing.to_str().unwrap());
+        assert_eq!(data["errors"][0]["reason"], "vanished");
+        assert_eq!(data["errors"][0]["os_error"], libc::ENOENT);
+        assert_eq!(data["skipped"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_skips_oversized_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let small = temp.path().join("small.txt");
+        std::fs::write(&small, "ok").unwrap();
+        // Sparse, so the limit is exceeded without writing the bytes
+        let large = temp.path().join("large.bin");
+        std::fs::File::create(&large).unwrap()
+            .set_len(engine::DEFAULT_MAX_FILE_SIZE + 1).unwrap();
+        let missing = temp.path().join("gone.txt");
+
+        let resp = handle_analyze(
+            "big-1".to_string(),
+            serde_json::json!({ "files": [
+                small.to_str().unwrap(), large.to_str().unwrap(), missing.to_str().unwrap()
+            ] }),
+        )
+        .await;
+
+        assert!(resp.success);
+        let data = resp.data.unwrap();
+        assert_eq!(data["files_analyzed"], 1);
+        assert_eq!(data["skipped"][0]["path"], large.to_str().unwrap());
+        assert_eq!(data["skipped"][0]["reason"], "too_large");
+        assert_eq!(data["errors"][0]["path"], missing.to_str().unwrap());
+    }
+
     #[test]
     fn test_handle_capabilities() {
         let resp = handle_capabilities("cap-test".to_string());
@@ -9,18 +9,22 @@ use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-/// File analysis result
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
-pub struct WasmFileAnalysis {
-    pub path: String,
-    pub size: u64,
-    pub file_type: String,
-    pub line_count: Option<u64>,
-    pub word_count: Option<u64>,
-    pub char_count: Option<u64>,
-    pub is_binary: bool,
-}
+pub mod archive;
+pub mod docmeta;
+pub mod elf;
+pub mod encoding;
+pub mod engine;
+pub mod entropy;
+pub mod hasher;
+pub mod markdown;
+pub mod ownership;
+pub mod sloc;
+pub mod sniff;
+pub mod storage;
+pub mod timestamps;
+pub mod xattrs;
+
+use engine::EngineOptions;
 
 /// Analysis report
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,64 +35,16 @@ pub struct WasmReport {
     pub timestamp: u64,
 }
 
-/// Analyze text content (WASM-compatible)
+/// Analyze text content (WASM-compatible); returns an [`engine::FileInfo`] as JSON
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn analyze_text(content: &str, filename: &str) -> String {
-    let lines = content.lines().count() as u64;
-    let words = content.split_whitespace().count() as u64;
-    let chars = content.chars().count() as u64;
-
-    let ext = filename
-        .rsplit('.')
-        .next()
-        .map(|s| s.to_lowercase())
-        .unwrap_or_else(|| "txt".to_string());
-
-    let analysis = WasmFileAnalysis {
-        path: filename.to_string(),
-        size: content.len() as u64,
-        file_type: ext,
-        line_count: Some(lines),
-        word_count: Some(words),
-        char_count: Some(chars),
-        is_binary: false,
-    };
-
-    serde_json::to_string(&analysis).unwrap_or_else(|_| "{}".to_string())
+    analyze_binary(content.as_bytes(), filename)
 }
 
-/// Analyze binary content (WASM-compatible)
+/// Analyze binary content (WASM-compatible); returns an [`engine::FileInfo`] as JSON
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn analyze_binary(data: &[u8], filename: &str) -> String {
-    let is_binary = data.iter().take(8192).any(|&b| b == 0);
-
-    let ext = filename
-        .rsplit('.')
-        .next()
-        .map(|s| s.to_lowercase())
-        .unwrap_or_else(|| "bin".to_string());
-
-    let (line_count, word_count, char_count) = if !is_binary {
-        let text = String::from_utf8_lossy(data);
-        (
-            Some(text.lines().count() as u64),
-            Some(text.split_whitespace().count() as u64),
-            Some(text.chars().count() as u64),
-        )
-    } else {
-        (None, None, None)
-    };
-
-    let analysis = WasmFileAnalysis {
-        path: filename.to_string(),
-        size: data.len() as u64,
-        file_type: ext,
-        line_count,
-        word_count,
-        char_count,
-        is_binary,
-    };
-
+    let analysis = engine::analyze_bytes(data, filename, &EngineOptions::default());
     serde_json::to_string(&analysis).unwrap_or_else(|_| "{}".to_string())
 }
 
@@ -114,7 +70,7 @@ mod tests {
         let content = "Hello World\nLine two\nLine three";
         let result = analyze_text(content, "test.txt");
 
-        let analysis: WasmFileAnalysis = serde_json::from_str(&result).unwrap();
+        let analysis: engine::FileInfo = serde_json::from_str(&result).unwrap();
         assert_eq!(analysis.line_count, Some(3));
         assert_eq!(analysis.word_count, Some(6)); // Hello, World, Line, two, Line, three
         assert!(!analysis.is_binary);
@@ -125,11 +81,30 @@ mod tests {
         let data = b"Hello World";
         let result = analyze_binary(data, "test.txt");
 
-        let analysis: WasmFileAnalysis = serde_json::from_str(&result).unwrap();
+        let analysis: engine::FileInfo = serde_json::from_str(&result).unwrap();
         assert!(!analysis.is_binary);
         assert_eq!(analysis.word_count, Some(2));
     }
 
+    #[test]
+    fn test_analyze_binary_sniffs_content() {
+        let result = analyze_binary(b"%PDF-1.4\n\0binary", "report.txt");
+
+        let analysis: engine::FileInfo = serde_json::from_str(&result).unwrap();
+        assert_eq!(analysis.content_type.mime_type, "application/pdf");
+        assert!(analysis.content_type.extension_mismatch);
+    }
+
+    #[test]
+    fn test_analyze_text_markdown() {
+        let result = analyze_text("# Title\n\n```js\nx\n```\n", "README.md");
+
+        let analysis: engine::FileInfo = serde_json::from_str(&result).unwrap();
+        let markdown = analysis.markdown.unwrap();
+        assert_eq!(markdown.headings[0].text, "Title");
+        assert_eq!(markdown.code_languages.get("js"), Some(&1));
+    }
+
     #[test]
     fn test_get_version() {
         let version = get_version();
@@ -8,6 +8,17 @@ mod config;
 mod ro_lock;
 mod picker;
 mod analyzer;
+mod audit;
+mod budget;
+mod cache;
+mod dedupe;
+mod disk_usage;
+mod diff;
+mod ignore_rules;
+mod scan_errors;
+mod secrets;
+mod symlinks;
+mod timeline;
 
 #[cfg(feature = "tui")]
 mod tui;
@@ -17,11 +28,12 @@ mod gui;
 
 mod ipc;
 
-use clap::Parser;
-use tracing::info;
+use clap::{Parser, Subcommand};
+use tracing::{info, warn};
 use anyhow::Result;
 
 use crate::auth::AuthGuard;
+use crate::cache::AnalysisCache;
 use crate::config::Config;
 use crate::ro_lock::ReadOnlyLock;
 
@@ -40,9 +52,25 @@ struct Cli {
     dest: Option<String>,
 
     /// Enforce read-only lock on source
-    #[arg(long, default_value = "true")]
+    #[arg(long, default_value_t = true, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
     ro_lock: bool,
 
+    /// Stream one JSON record per file (NDJSON) instead of a single report
+    #[arg(long)]
+    ndjson: bool,
+
+    /// Ignore and do not update the incremental analysis cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// List entries of zip/jar/tar/tar.gz archives as virtual files
+    #[arg(long)]
+    archives: bool,
+
+    /// Fail the run when any file could not be analyzed
+    #[arg(long)]
+    strict: bool,
+
     /// Run in TUI mode
     #[cfg(feature = "tui")]
     #[arg(long)]
@@ -64,28 +92,83 @@ struct Cli {
     /// Verbose output
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two exported analysis reports
+    Diff {
+        /// Older report (JSON export)
+        old: String,
+
+        /// Newer report (JSON export)
+        new: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "human")]
+        format: diff::DiffFormat,
+
+        /// Write the diff to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Build a MAC(B) timeline from an exported analysis report
+    Timeline {
+        /// Report (JSON export)
+        report: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "bodyfile")]
+        format: timeline::TimelineFormat,
+
+        /// Write the timeline to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            // --help/--version exit here too; the production fail-safe still applies
+            let _ = AuthGuard::init();
+            e.exit();
+        }
+    };
 
     // Plugin mode skips auth guard (host handles auth)
     if cli.plugin_mode {
-        init_logging(0); // Quiet mode for IPC
+        init_logging(0, false); // Quiet mode for IPC
         return ipc::run_plugin_server().await;
     }
 
     // CRITICAL: Auth guard MUST be first - panics in prod if auth disabled
     let _auth_guard = AuthGuard::init()?;
 
-    // Initialize logging
-    init_logging(cli.verbose);
+    // Initialize logging; keep stdout clean when results are written there
+    init_logging(cli.verbose, cli.dest.as_deref() == Some("-"));
 
     info!("💎 Diamond Drill v{} starting...", env!("CARGO_PKG_VERSION"));
 
+    match cli.command {
+        Some(Command::Diff { old, new, format, output }) => {
+            return run_diff(&old, &new, format, output.as_deref()).await;
+        }
+        Some(Command::Timeline { report, format, output }) => {
+            let results = diff::load_report(&report)?;
+            return timeline::export(&results.files, format, output.as_deref().unwrap_or("-")).await;
+        }
+        None => {}
+    }
+
     // Load config
-    let config = Config::load(&cli.config).await?;
+    let mut config = Config::load(&cli.config).await?;
+    config.archives.enabled |= cli.archives;
 
     // Determine mode and run
     #[cfg(feature = "gui")]
@@ -104,7 +187,7 @@ async fn main() -> Result<()> {
     run_cli(cli, config).await
 }
 
-async fn run_cli(cli: Cli, config: Config) -> Result<()> {
+async fn run_cli(cli: Cli, mut config: Config) -> Result<()> {
     let source = match cli.source {
         Some(s) => s,
         None => {
@@ -123,24 +206,99 @@ async fn run_cli(cli: Cli, config: Config) -> Result<()> {
     // Acquire read-only lock if enabled
     let _ro_guard = if cli.ro_lock {
         info!("🔒 Acquiring read-only lock on source...");
+        config.analysis.ro_locked = true;
         Some(ReadOnlyLock::acquire(&source).await?)
     } else {
         None
     };
 
+    let ndjson = cli.ndjson || config.export.format == "ndjson";
+
+    // Open the incremental cache, which must never live inside the locked source
+    let cache = if cli.no_cache || !config.cache.enabled {
+        None
+    } else if ndjson {
+        // The index holds every file until saved, defeating streaming
+        info!("Analysis cache not used when streaming NDJSON");
+        None
+    } else {
+        let path = AnalysisCache::location(&source, &config);
+        match _ro_guard.as_ref().map(|lock| lock.ensure_outside(&path)) {
+            Some(Err(e)) => {
+                warn!("Analysis cache disabled: {}", e);
+                None
+            }
+            _ => Some(AnalysisCache::open(path, &source, &config)),
+        }
+    };
+
     // Run analysis
     info!("📊 Analyzing {}...", source);
-    let results = analyzer::analyze(&source, &config).await?;
+    let (errors, cache) = if ndjson {
+        if config.secrets.enabled {
+            warn!("Secret scan skipped: not available when streaming NDJSON");
+        }
+        info!("📤 Streaming NDJSON to {}...", dest);
+        let summary = analyzer::analyze_ndjson(&source, &config, &dest).await?;
+        (summary.errors, cache)
+    } else {
+        let (mut results, cache) = analyzer::analyze_cached(&source, &config, cache).await?;
+
+        // Secret scanning only reads through the lock
+        if config.secrets.enabled {
+            match _ro_guard.as_ref() {
+                Some(lock) => tokio::task::block_in_place(|| {
+                    secrets::scan_results(lock, &mut results, &config.secrets)
+                })?,
+                None => warn!("Secret scan skipped: requires --ro-lock"),
+            }
+        }
+
+        // Export results, or only their timeline
+        info!("📤 Exporting to {}...", dest);
+        match timeline::TimelineFormat::from_export(&config.export.format) {
+            Some(format) => timeline::export(&results.files, format, &dest).await?,
+            None => analyzer::export(&results, &dest).await?,
+        }
+        (results.errors, cache)
+    };
+
+    if let Some(cache) = cache {
+        let path = cache.path().to_path_buf();
+        if let Err(e) = cache.save() {
+            warn!("Failed to save analysis cache {}: {}", path.display(), e);
+        }
+    }
 
-    // Export results
-    info!("📤 Exporting to {}...", dest);
-    analyzer::export(&results, &dest).await?;
+    // The report is written either way; --strict only changes the exit status
+    if let Some(first) = errors.first().filter(|_| cli.strict) {
+        anyhow::bail!(
+            "{} paths could not be analyzed (first: {}: {})",
+            errors.len(),
+            first.path,
+            first.message
+        );
+    }
 
     info!("✅ Complete! Results exported to {}", dest);
     Ok(())
 }
 
-fn init_logging(verbosity: u8) {
+async fn run_diff(old: &str, new: &str, format: diff::DiffFormat, output: Option<&str>) -> Result<()> {
+    let report = diff::diff_reports(&diff::load_report(old)?, &diff::load_report(new)?);
+    let rendered = diff::render(&report, format)?;
+
+    match output {
+        Some(path) => {
+            tokio::fs::write(path, rendered).await?;
+            info!("✅ Diff written to {}", path);
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+fn init_logging(verbosity: u8, to_stderr: bool) {
     let filter = match verbosity {
         0 => "warn",
         1 => "info",
@@ -148,8 +306,13 @@ fn init_logging(verbosity: u8) {
         _ => "trace",
     };
 
-    tracing_subscriber::fmt()
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_target(false)
-        .init();
+        .with_target(false);
+
+    if to_stderr {
+        subscriber.with_writer(std::io::stderr).init();
+    } else {
+        subscriber.init();
+    }
 }
@@ -0,0 +1,406 @@
+//! Markdown Analysis Module
+//!
+//! Document structure for Markdown files: heading outline, front matter keys,
+//! link and image targets, code-block languages, task-list progress and an
+//! estimated reading time. [`check_links`] then resolves relative links
+//! across a set of analyzed files and reports the ones that point nowhere.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{CodeBlockKind, Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::FileInfo;
+
+/// Extensions treated as Markdown
+pub const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn"];
+
+/// Average silent reading speed used for `reading_time_minutes`
+const WORDS_PER_MINUTE: usize = 200;
+
+/// A heading in document order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    /// Fragment that links to this heading (`{#id}` or GitHub-style slug)
+    pub anchor: String,
+}
+
+/// Structure extracted from a Markdown document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownInfo {
+    pub headings: Vec<Heading>,
+    pub front_matter_keys: Vec<String>,
+    pub links: Vec<String>,
+    pub images: Vec<String>,
+    pub code_blocks: usize,
+    /// Fenced block count per info-string language
+    pub code_languages: BTreeMap<String, usize>,
+    pub tasks_total: usize,
+    pub tasks_done: usize,
+    /// Prose words (code excluded)
+    pub words: usize,
+    pub reading_time_minutes: usize,
+}
+
+/// A relative link whose target does not exist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    /// Markdown file containing the link
+    pub file: String,
+    pub target: String,
+    /// `missing file` or `missing anchor`
+    pub reason: String,
+}
+
+pub fn is_markdown_extension(ext: &str) -> bool {
+    MARKDOWN_EXTENSIONS.contains(&ext)
+}
+
+/// Extract the structure of a Markdown document
+pub fn analyze(text: &str) -> MarkdownInfo {
+    let options = Options::ENABLE_TASKLISTS
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+
+    let mut info = MarkdownInfo::default();
+    let mut slugs: HashMap<String, usize> = HashMap::new();
+    let mut heading: Option<(u8, Option<String>, String)> = None;
+    let mut front_matter: Option<(bool, String)> = None;
+    let mut in_code = false;
+
+    for event in Parser::new_ext(text, options) {
+        match event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                heading = Some((level as u8, id.map(|i| i.to_string()), String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, id, text)) = heading.take() {
+                    let text = text.trim().to_string();
+                    let anchor = id.unwrap_or_else(|| unique_slug(&text, &mut slugs));
+                    info.headings.push(Heading { level, text, anchor });
+                }
+            }
+            Event::Start(Tag::MetadataBlock(kind)) => {
+                let toml = matches!(kind, pulldown_cmark::MetadataBlockKind::PlusesStyle);
+                front_matter = Some((toml, String::new()));
+            }
+            Event::End(TagEnd::MetadataBlock(_)) => {
+                if let Some((toml, block)) = front_matter.take() {
+                    info.front_matter_keys = front_matter_keys(&block, toml);
+                }
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                in_code = true;
+                info.code_blocks += 1;
+                if let CodeBlockKind::Fenced(lang) = kind {
+                    let lang = lang.split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+                        .next()
+                        .unwrap_or_default()
+                        .to_lowercase();
+                    if !lang.is_empty() {
+                        *info.code_languages.entry(lang).or_insert(0) += 1;
+                    }
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Start(Tag::Link { link_type, dest_url, .. })
+                if link_type != LinkType::Email && !dest_url.is_empty() =>
+            {
+                info.links.push(dest_url.to_string());
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                info.images.push(dest_url.to_string());
+            }
+            Event::TaskListMarker(done) => {
+                info.tasks_total += 1;
+                if done {
+                    info.tasks_done += 1;
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, block)) = front_matter.as_mut() {
+                    block.push_str(&text);
+                    continue;
+                }
+                if let Some((_, _, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(&text);
+                }
+                if !in_code {
+                    info.words += text.split_whitespace().count();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    info.reading_time_minutes = info.words.div_ceil(WORDS_PER_MINUTE);
+    info
+}
+
+/// Report relative links and anchors in `files` that do not resolve.
+///
+/// Targets are resolved against each document's directory and must be one of
+/// `files` or a directory holding one; fragments are checked against the
+/// headings of analyzed Markdown files.
+pub fn check_links(files: &[FileInfo]) -> Vec<BrokenLink> {
+    let mut known = HashSet::new();
+    for file in files.iter().filter(|f| f.archive_entry.is_none()) {
+        let path = normalize(Path::new(&file.path));
+        known.extend(path.ancestors().map(Path::to_path_buf));
+    }
+    let anchors: HashMap<PathBuf, HashSet<&str>> = files
+        .iter()
+        .filter_map(|f| {
+            let md = f.markdown.as_ref()?;
+            let anchors = md.headings.iter().map(|h| h.anchor.as_str()).collect();
+            Some((normalize(Path::new(&f.path)), anchors))
+        })
+        .collect();
+
+    let mut broken = Vec::new();
+    // Links inside archives cannot be resolved on disk
+    for file in files.iter().filter(|f| f.archive_entry.is_none()) {
+        let Some(md) = &file.markdown else { continue };
+        let doc = normalize(Path::new(&file.path));
+        let dir = doc.parent().unwrap_or(Path::new(""));
+
+        let mut seen = HashSet::new();
+        for target in md.links.iter().chain(&md.images) {
+            if !seen.insert(target) || !is_relative_target(target) {
+                continue;
+            }
+
+            let (path_part, fragment) = match target.split_once('#') {
+                Some((p, f)) => (p, Some(f)),
+                None => (target.as_str(), None),
+            };
+            let path_part = path_part.split('?').next().unwrap_or_default();
+
+            let resolved = if path_part.is_empty() {
+                doc.clone()
+            } else {
+                normalize(&dir.join(percent_decode(path_part)))
+            };
+
+            let reason = if !path_part.is_empty() && !known.contains(&resolved) {
+                Some("missing file")
+            } else {
+                fragment
+                    .filter(|f| !f.is_empty())
+                    .and_then(|f| {
+                        let known = anchors.get(&resolved)?;
+                        (!known.contains(percent_decode(f).as_str())).then_some("missing anchor")
+                    })
+            };
+
+            if let Some(reason) = reason {
+                broken.push(BrokenLink {
+                    file: file.path.clone(),
+                    target: target.clone(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+    }
+    broken
+}
+
+/// Links with a scheme (`https:`, `mailto:`) or a root path are not checked
+fn is_relative_target(target: &str) -> bool {
+    if target.starts_with('/') || target.starts_with("//") {
+        return false;
+    }
+    match target.find(':') {
+        Some(colon) => {
+            let scheme = &target[..colon];
+            // A colon after the first path separator is part of the path
+            scheme.contains(['/', '#', '?'])
+                || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+        }
+        None => true,
+    }
+}
+
+/// GitHub-style heading slug, suffixed `-1`, `-2`... for repeats
+fn unique_slug(text: &str, seen: &mut HashMap<String, usize>) -> String {
+    let base: String = text
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect();
+
+    let count = seen.entry(base.clone()).or_insert(0);
+    let slug = if *count == 0 { base } else { format!("{}-{}", base, count) };
+    *count += 1;
+    slug
+}
+
+/// Top-level keys of a YAML (`---`) or TOML (`+++`) front matter block
+fn front_matter_keys(block: &str, toml: bool) -> Vec<String> {
+    let separator = if toml { '=' } else { ':' };
+    block
+        .lines()
+        // Keys after the first TOML table header belong to that table
+        .take_while(|line| !(toml && line.starts_with('[')))
+        .filter(|line| !line.starts_with([' ', '\t', '#', '-', '[']))
+        .filter_map(|line| line.split_once(separator))
+        .map(|(key, _)| key.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// Decode `%XX` escapes (link targets are URLs, paths on disk are not)
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Lexically resolve `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{analyze_path, EngineOptions};
+    use std::fs;
+
+    const DOC: &str = "---
+title: \"Guide\"
+tags:
+  - a
+draft: false
+---
+
+# Getting Started
+
+Some intro text with a [link](other.md#setup) and ![logo](img/logo.png).
+
+## Getting Started
+
+- [x] install
+- [ ] configure
+- [X] run
+
+```rust
+fn main() {}
+```
+
+```
+plain
+```
+
+See <https://example.com> and [mail](mailto:a@b.c).
+";
+
+    #[test]
+    fn test_document_structure() {
+        let md = analyze(DOC);
+
+        assert_eq!(md.front_matter_keys, vec!["title", "tags", "draft"]);
+        assert_eq!(md.headings.len(), 2);
+        assert_eq!(md.headings[0].level, 1);
+        assert_eq!(md.headings[0].anchor, "getting-started");
+        assert_eq!(md.headings[1].anchor, "getting-started-1");
+        assert_eq!(md.links, vec!["other.md#setup", "https://example.com", "mailto:a@b.c"]);
+        assert_eq!(md.images, vec!["img/logo.png"]);
+        assert_eq!(md.code_blocks, 2);
+        assert_eq!(md.code_languages.get("rust"), Some(&1));
+        assert_eq!((md.tasks_done, md.tasks_total), (2, 3));
+        assert_eq!(md.reading_time_minutes, 1);
+        assert!(md.words > 10);
+    }
+
+    #[test]
+    fn test_toml_front_matter_and_custom_ids() {
+        let md = analyze("+++\ntitle = \"x\"\n[extra]\nkey = 1\n+++\n\n# Intro {#start}\n");
+        assert_eq!(md.front_matter_keys, vec!["title"]);
+        assert_eq!(md.headings[0].anchor, "start");
+        assert_eq!(md.headings[0].text, "Intro");
+    }
+
+    #[test]
+    fn test_relative_targets() {
+        assert!(is_relative_target("docs/a.md"));
+        assert!(is_relative_target("#section"));
+        assert!(is_relative_target("./a:b.md"));
+        assert!(!is_relative_target("https://example.com"));
+        assert!(!is_relative_target("mailto:x@y.z"));
+        assert!(!is_relative_target("/abs/path"));
+        assert_eq!(percent_decode("my%20file.md"), "my file.md");
+        assert_eq!(normalize(Path::new("a/b/../c/./d")), PathBuf::from("a/c/d"));
+    }
+
+    #[test]
+    fn test_check_links() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir(temp.path().join("docs")).unwrap();
+        fs::write(temp.path().join("README.md"), "\
+# Readme
+[ok](docs/guide.md#install) [bad anchor](docs/guide.md#nope) [gone](missing.md)
+[self](#readme) [bad self](#other) [dir](docs/) [web](https://x.y/z) [unlisted](notes.txt)
+![img](docs/pic.png)
+").unwrap();
+        fs::write(temp.path().join("docs/guide.md"), "# Install\n[up](../README.md#readme)\n").unwrap();
+        // On disk but not analyzed
+        fs::write(temp.path().join("notes.txt"), "notes").unwrap();
+
+        let options = EngineOptions::default();
+        let files: Vec<FileInfo> = ["README.md", "docs/guide.md"]
+            .iter()
+            .map(|f| analyze_path(&temp.path().join(f), &options).unwrap())
+            .collect();
+        let broken = check_links(&files);
+
+        let targets: Vec<(&str, &str)> = broken.iter()
+            .map(|b| (b.target.as_str(), b.reason.as_str()))
+            .collect();
+        assert_eq!(targets, vec![
+            ("docs/guide.md#nope", "missing anchor"),
+            ("missing.md", "missing file"),
+            ("#other", "missing anchor"),
+            ("notes.txt", "missing file"),
+            ("docs/pic.png", "missing file"),
+        ]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Ownership Module
+//!
+//! Unix owner, group and full mode bits of a file, including setuid, setgid
+//! and sticky. User and group names are resolved once per id and remembered
+//! for the life of the process, so analyzing a large tree costs one passwd
+//! lookup per distinct owner.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_ISVTX: u32 = 0o1000;
+const S_IWOTH: u32 = 0o0002;
+
+/// Owner and mode of a file on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ownership {
+    /// Permission and special bits in octal (`4755`)
+    pub mode: String,
+    pub uid: u32,
+    pub gid: u32,
+    /// `None` when no account has this uid
+    #[serde(default)]
+    pub user: Option<String>,
+    /// `None` when no group has this gid
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub setuid: bool,
+    #[serde(default)]
+    pub setgid: bool,
+    #[serde(default)]
+    pub sticky: bool,
+    #[serde(default)]
+    pub world_writable: bool,
+}
+
+impl Ownership {
+    /// Ownership from raw ids and `st_mode`, resolving names
+    pub fn new(uid: u32, gid: u32, mode: u32) -> Self {
+        Self {
+            mode: format!("{:o}", mode & 0o7777),
+            uid,
+            gid,
+            user: user_name(uid),
+            group: group_name(gid),
+            setuid: mode & S_ISUID != 0,
+            setgid: mode & S_ISGID != 0,
+            sticky: mode & S_ISVTX != 0,
+            world_writable: mode & S_IWOTH != 0,
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Self::new(metadata.uid(), metadata.gid(), metadata.mode())
+    }
+
+    /// Owner name, or the numeric uid when it has no account
+    pub fn owner_label(&self) -> String {
+        self.user.clone().unwrap_or_else(|| self.uid.to_string())
+    }
+}
+
+type NameCache = Mutex<HashMap<u32, Option<String>>>;
+
+fn cached(cache: &'static OnceLock<NameCache>, id: u32, resolve: fn(u32) -> Option<String>) -> Option<String> {
+    let cache = cache.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(name) = cache.lock().ok().and_then(|names| names.get(&id).cloned()) {
+        return name;
+    }
+    let name = resolve(id);
+    if let Ok(mut names) = cache.lock() {
+        names.insert(id, name.clone());
+    }
+    name
+}
+
+/// Account name of `uid`
+pub fn user_name(uid: u32) -> Option<String> {
+    static USERS: OnceLock<NameCache> = OnceLock::new();
+    cached(&USERS, uid, lookup_user)
+}
+
+/// Group name of `gid`
+pub fn group_name(gid: u32) -> Option<String> {
+    static GROUPS: OnceLock<NameCache> = OnceLock::new();
+    cached(&GROUPS, gid, lookup_group)
+}
+
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> Option<String> {
+    nix::unistd::User::from_uid(uid.into()).ok().flatten().map(|u| u.name)
+}
+
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> Option<String> {
+    nix::unistd::Group::from_gid(gid.into()).ok().flatten().map(|g| g.name)
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_bits() {
+        let passwd = Ownership::new(0, 0, 0o104755);
+        assert_eq!(passwd.mode, "4755");
+        assert!(passwd.setuid && !passwd.setgid && !passwd.sticky && !passwd.world_writable);
+        assert_eq!(passwd.user.as_deref(), Some("root"));
+        assert_eq!(passwd.owner_label(), "root");
+
+        let tmp = Ownership::new(0, 0, 0o041777);
+        assert_eq!(tmp.mode, "1777");
+        assert!(tmp.sticky && tmp.world_writable);
+
+        // Ids far above any real account
+        let orphan = Ownership::new(4_000_000_001, 4_000_000_001, 0o2644);
+        assert!(orphan.setgid);
+        assert_eq!(orphan.user, None);
+        assert_eq!(orphan.owner_label(), "4000000001");
+    }
+}
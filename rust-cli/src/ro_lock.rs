@@ -177,8 +177,7 @@ impl ReadOnlyLock {
         fs::create_dir_all(&mount_point)
             .map_err(|e| RoLockError::MountError(e.to_string()))?;
 
-        let source = self.loop_device.as_ref()
-            .map(|s| s.as_str())
+        let source = self.loop_device.as_deref()
             .unwrap_or_else(|| self.path.to_str().unwrap());
 
         let output = Command::new("mount")
@@ -237,6 +236,43 @@ impl ReadOnlyLock {
         })
     }
 
+    /// Verify that a path we intend to write (e.g. the analysis cache) lies
+    /// outside the locked area. The path itself need not exist yet; relative
+    /// paths are taken against the current directory.
+    pub fn ensure_outside<P: AsRef<Path>>(&self, path: P) -> Result<(), RoLockError> {
+        let path = path.as_ref();
+        let absolute = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()?.join(path)
+        };
+
+        // Resolve the deepest existing ancestor so symlinks cannot smuggle us in
+        let mut existing = absolute.as_path();
+        while !existing.exists() {
+            match existing.parent() {
+                Some(parent) => existing = parent,
+                None => break,
+            }
+        }
+        let resolved = existing.canonicalize()?;
+
+        let mut locked = vec![self.path.canonicalize()?];
+        if let Some(mount_point) = &self.mount_point {
+            locked.push(mount_point.canonicalize()?);
+        }
+
+        if let Some(area) = locked.iter().find(|area| resolved.starts_with(area)) {
+            return Err(RoLockError::WriteAttemptBlocked(format!(
+                "Path {} is inside locked area {}",
+                path.display(),
+                area.display()
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Get the effective path to use (mount point if mounted, original otherwise)
     pub fn effective_path(&self) -> &Path {
         self.mount_point.as_ref().unwrap_or(&self.path)
@@ -390,6 +426,22 @@ mod tests {
         assert!(content.contains("test content"));
     }
 
+    #[tokio::test]
+    async fn test_ensure_outside() {
+        let locked = tempdir().unwrap();
+        let elsewhere = tempdir().unwrap();
+        let lock = ReadOnlyLock::acquire(locked.path().to_str().unwrap()).await.unwrap();
+
+        assert!(lock.ensure_outside(elsewhere.path().join("cache/index.json")).is_ok());
+        assert!(matches!(
+            lock.ensure_outside(locked.path().join("not/yet/created.json")),
+            Err(RoLockError::WriteAttemptBlocked(_))
+        ));
+
+        // Relative paths resolve against the current directory
+        assert!(lock.ensure_outside("cache/index.json").is_ok());
+    }
+
     #[test]
     fn test_lock_level_detection() {
         let temp = tempdir().unwrap();
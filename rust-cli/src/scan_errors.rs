@@ -0,0 +1,206 @@
+//! Scan Errors Module
+//!
+//! Accounting for everything the analyzer did not analyze, so a report never
+//! silently leaves files out. Entries that could not be read (permissions,
+//! files vanishing mid-scan, I/O failures) are errors, which `--strict`
+//! turns into a failed run; entries left out on purpose (over
+//! `max_file_size`, excluded by a rule, not a regular file, beyond a depth or
+//! filesystem limit, dropped from the queue by the time budget) are skips.
+
+use std::fs::FileType;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use diamond_drill::engine::EngineError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueReason {
+    /// Read or directory listing refused (`EACCES`, `EPERM`)
+    PermissionDenied,
+    /// Gone between listing and reading (`ENOENT`)
+    Vanished,
+    /// Any other I/O failure
+    Io,
+    /// Over `[analysis] max_file_size`
+    TooLarge,
+    /// Matched an exclude pattern or ignore file, or no include pattern
+    Excluded,
+    /// FIFO, socket or device node
+    SpecialFile,
+    /// Below `[analysis] max_depth`
+    TooDeep,
+    /// Mount point on another filesystem under `[analysis] one_file_system`
+    OtherFilesystem,
+    /// Queued, but `[analysis] time_budget` ran out before it was analyzed
+    TimeBudget,
+}
+
+impl IssueReason {
+    /// Whether this reason is a failure rather than a deliberate skip
+    pub fn is_error(self) -> bool {
+        matches!(self, IssueReason::PermissionDenied | IssueReason::Vanished | IssueReason::Io)
+    }
+}
+
+/// A path the analyzer could not or would not analyze
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanIssue {
+    pub path: String,
+    pub reason: IssueReason,
+    /// `errno` of the failed call, when there was one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_error: Option<i32>,
+    pub message: String,
+}
+
+impl ScanIssue {
+    pub fn new(path: &Path, reason: IssueReason, message: impl Into<String>) -> Self {
+        Self {
+            path: path.display().to_string(),
+            reason,
+            os_error: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn from_io(path: &Path, error: &io::Error) -> Self {
+        let reason = match error.kind() {
+            io::ErrorKind::PermissionDenied => IssueReason::PermissionDenied,
+            io::ErrorKind::NotFound => IssueReason::Vanished,
+            _ => IssueReason::Io,
+        };
+        Self {
+            os_error: error.raw_os_error(),
+            ..Self::new(path, reason, error.to_string())
+        }
+    }
+
+    pub fn from_engine(path: &Path, error: &EngineError) -> Self {
+        match error {
+            EngineError::TooLarge { .. } => Self::new(path, IssueReason::TooLarge, error.to_string()),
+            EngineError::IoError(e) => Self::from_io(path, e),
+        }
+    }
+
+    /// A skipped entry that is neither a regular file nor a directory
+    pub fn special(path: &Path, file_type: FileType) -> Self {
+        Self::new(path, IssueReason::SpecialFile, special_kind(file_type))
+    }
+}
+
+#[cfg(unix)]
+fn special_kind(file_type: FileType) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() {
+        "block device"
+    } else if file_type.is_char_device() {
+        "character device"
+    } else {
+        "special file"
+    }
+}
+
+#[cfg(not(unix))]
+fn special_kind(_file_type: FileType) -> &'static str {
+    "special file"
+}
+
+/// Issues recorded by the walker and analysis workers
+#[derive(Debug, Default)]
+pub struct ScanIssues {
+    pub errors: Vec<ScanIssue>,
+    pub skipped: Vec<ScanIssue>,
+}
+
+impl ScanIssues {
+    /// Record from any thread
+    pub fn record(issues: &Mutex<Self>, issue: ScanIssue) {
+        if let Ok(mut issues) = issues.lock() {
+            issues.push(issue);
+        }
+    }
+
+    pub fn push(&mut self, issue: ScanIssue) {
+        if issue.reason.is_error() {
+            self.errors.push(issue);
+        } else {
+            self.skipped.push(issue);
+        }
+    }
+
+    /// Both lists ordered by path, as workers finish in any order
+    pub fn sorted(mut self) -> Self {
+        self.errors.sort_by(|a, b| a.path.cmp(&b.path));
+        self.skipped.sort_by(|a, b| a.path.cmp(&b.path));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer;
+    use crate::config::Config;
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_errors_and_skips_are_reported() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("ok.txt"), "fine").unwrap();
+        fs::write(root.join("big.bin"), vec![0u8; 4096]).unwrap();
+        fs::write(root.join("notes.tmp"), "scratch").unwrap();
+        nix::unistd::mkfifo(&root.join("pipe"), nix::sys::stat::Mode::S_IRWXU).unwrap();
+        fs::create_dir(root.join("locked")).unwrap();
+        fs::write(root.join("locked/inside.txt"), "hidden").unwrap();
+        fs::set_permissions(root.join("locked"), Permissions::from_mode(0o000)).unwrap();
+        // Root reads through permission bits; nothing to refuse then
+        let enforced = fs::read_dir(root.join("locked")).is_err();
+
+        let mut config = Config::default();
+        config.analysis.max_file_size = 1024;
+        config.analysis.exclude_patterns = vec!["*.tmp".to_string()];
+        let source = root.to_str().unwrap();
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        fs::set_permissions(root.join("locked"), Permissions::from_mode(0o755)).unwrap();
+
+        let summary = |issues: &[ScanIssue]| -> Vec<(String, IssueReason)> {
+            issues.iter()
+                .map(|i| (i.path.strip_prefix(source).unwrap().to_string(), i.reason))
+                .collect()
+        };
+        assert_eq!(summary(&results.skipped), [
+            ("/big.bin".to_string(), IssueReason::TooLarge),
+            ("/notes.tmp".to_string(), IssueReason::Excluded),
+            ("/pipe".to_string(), IssueReason::SpecialFile),
+        ]);
+        assert_eq!(results.skipped[2].message, "fifo");
+
+        if enforced {
+            assert_eq!(summary(&results.errors), [("/locked".to_string(), IssueReason::PermissionDenied)]);
+            assert_eq!(results.errors[0].os_error, Some(libc::EACCES));
+        }
+    }
+
+    #[test]
+    fn test_io_classification() {
+        let path = Path::new("/srv/gone");
+        let gone = ScanIssue::from_io(path, &io::Error::from_raw_os_error(libc::ENOENT));
+        assert_eq!((gone.reason, gone.os_error), (IssueReason::Vanished, Some(libc::ENOENT)));
+        let denied = ScanIssue::from_io(path, &io::Error::from_raw_os_error(libc::EPERM));
+        assert_eq!(denied.reason, IssueReason::PermissionDenied);
+        let large = ScanIssue::from_engine(path, &EngineError::TooLarge { size: 9, limit: 1 });
+        assert!(!large.reason.is_error() && large.os_error.is_none());
+        assert!(ScanIssue::from_io(path, &io::Error::from_raw_os_error(libc::EIO)).reason.is_error());
+    }
+}
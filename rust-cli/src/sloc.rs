@@ -0,0 +1,423 @@
+//! Source Lines Module
+//!
+//! tokei-style line counts for source files. The language comes from the
+//! file name (`Makefile`, `Dockerfile`), the extension, or the `#!` line of
+//! extensionless scripts. Each line is counted once as code, comment or
+//! blank: a line with any code on it is code, and comment markers inside
+//! string literals are ignored. Block comments may span lines and nest where
+//! the language allows it (Rust, Swift, Haskell...).
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::FileInfo;
+
+/// Comment and string syntax of a language
+struct Language {
+    name: &'static str,
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    /// Block comments nest (`/* /* */ */` is one comment)
+    nested: bool,
+    /// String delimiters; `\` escapes inside them
+    quotes: &'static [char],
+    extensions: &'static [&'static str],
+    /// File names, also with a suffix (`Dockerfile.dev`); case-sensitive
+    filenames: &'static [&'static str],
+    /// Interpreter names in a `#!` line, version suffix stripped
+    interpreters: &'static [&'static str],
+}
+
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+const C_LINE: &[&str] = &["//"];
+const HASH: &[&str] = &["#"];
+const DQ: &[char] = &['"'];
+const DQ_SQ: &[char] = &['"', '\''];
+
+const fn lang(
+    name: &'static str,
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    quotes: &'static [char],
+    extensions: &'static [&'static str],
+) -> Language {
+    Language { name, line, block, nested: false, quotes, extensions, filenames: &[], interpreters: &[] }
+}
+
+const LANGUAGES: &[Language] = &[
+    Language { nested: true, ..lang("Rust", C_LINE, C_BLOCK, DQ, &["rs"]) },
+    lang("C", C_LINE, C_BLOCK, DQ_SQ, &["c", "h"]),
+    lang("C++", C_LINE, C_BLOCK, DQ_SQ, &["cc", "cpp", "cxx", "c++", "hh", "hpp", "hxx", "h++", "ino"]),
+    lang("C#", C_LINE, C_BLOCK, DQ_SQ, &["cs"]),
+    lang("Objective-C", C_LINE, C_BLOCK, DQ_SQ, &["m", "mm"]),
+    lang("Java", C_LINE, C_BLOCK, DQ_SQ, &["java"]),
+    Language { nested: true, ..lang("Kotlin", C_LINE, C_BLOCK, DQ_SQ, &["kt", "kts"]) },
+    Language { nested: true, ..lang("Scala", C_LINE, C_BLOCK, DQ_SQ, &["scala", "sc"]) },
+    Language { nested: true, ..lang("Swift", C_LINE, C_BLOCK, DQ, &["swift"]) },
+    Language { nested: true, ..lang("Dart", C_LINE, C_BLOCK, DQ_SQ, &["dart"]) },
+    lang("Go", C_LINE, C_BLOCK, &['"', '\'', '`'], &["go"]),
+    lang("Zig", C_LINE, &[], DQ_SQ, &["zig"]),
+    Language {
+        interpreters: &["node", "nodejs", "deno", "bun"],
+        ..lang("JavaScript", C_LINE, C_BLOCK, &['"', '\'', '`'], &["js", "mjs", "cjs", "jsx"])
+    },
+    Language {
+        interpreters: &["ts-node"],
+        ..lang("TypeScript", C_LINE, C_BLOCK, &['"', '\'', '`'], &["ts", "mts", "cts", "tsx"])
+    },
+    lang("CSS", &[], C_BLOCK, DQ_SQ, &["css"]),
+    lang("SCSS", C_LINE, C_BLOCK, DQ_SQ, &["scss", "sass", "less"]),
+    Language { interpreters: &["php"], ..lang("PHP", &["//", "#"], C_BLOCK, DQ_SQ, &["php"]) },
+    // Docstrings count as comments
+    Language {
+        interpreters: &["python", "pypy"],
+        filenames: &["SConstruct", "SConscript"],
+        ..lang("Python", HASH, &[("\"\"\"", "\"\"\""), ("'''", "'''")], DQ_SQ, &["py", "pyw", "pyi"])
+    },
+    Language {
+        interpreters: &["ruby"],
+        filenames: &["Rakefile", "Gemfile", "Vagrantfile"],
+        ..lang("Ruby", HASH, &[("=begin", "=end")], DQ_SQ, &["rb", "rake", "gemspec"])
+    },
+    Language {
+        interpreters: &["perl"],
+        ..lang("Perl", HASH, &[("=pod", "=cut")], DQ_SQ, &["pl", "pm"])
+    },
+    Language {
+        interpreters: &["sh", "bash", "zsh", "dash", "ksh", "ash"],
+        filenames: &[".bashrc", ".bash_profile", ".profile", ".zshrc"],
+        ..lang("Shell", HASH, &[], DQ_SQ, &["sh", "bash", "zsh", "ksh"])
+    },
+    Language { interpreters: &["fish"], ..lang("Fish", HASH, &[], DQ_SQ, &["fish"]) },
+    Language {
+        interpreters: &["pwsh", "powershell"],
+        ..lang("PowerShell", HASH, &[("<#", "#>")], DQ_SQ, &["ps1", "psm1", "psd1"])
+    },
+    Language { interpreters: &["lua"], ..lang("Lua", &["--"], &[("--[[", "]]")], DQ_SQ, &["lua"]) },
+    Language { interpreters: &["Rscript"], ..lang("R", HASH, &[], DQ_SQ, &["r"]) },
+    Language {
+        nested: true,
+        interpreters: &["julia"],
+        ..lang("Julia", HASH, &[("#=", "=#")], DQ, &["jl"])
+    },
+    Language { interpreters: &["elixir"], ..lang("Elixir", HASH, &[], DQ_SQ, &["ex", "exs"]) },
+    lang("Erlang", &["%"], &[], DQ, &["erl", "hrl"]),
+    Language { nested: true, ..lang("Haskell", &["--"], &[("{-", "-}")], DQ, &["hs", "lhs"]) },
+    Language { nested: true, ..lang("OCaml", &[], &[("(*", "*)")], DQ, &["ml", "mli"]) },
+    lang("SQL", &["--"], C_BLOCK, DQ_SQ, &["sql"]),
+    lang("HTML", &[], &[("<!--", "-->")], &[], &["html", "htm", "xhtml", "vue", "svelte"]),
+    lang("XML", &[], &[("<!--", "-->")], &[], &["xml", "xsd", "xsl", "xslt", "svg", "plist"]),
+    lang("YAML", HASH, &[], DQ_SQ, &["yml", "yaml"]),
+    Language { filenames: &["Cargo.lock", "Pipfile"], ..lang("TOML", HASH, &[], DQ_SQ, &["toml"]) },
+    lang("JSON", &[], &[], DQ, &["json"]),
+    Language {
+        filenames: &["Makefile", "makefile", "GNUmakefile"],
+        interpreters: &["make"],
+        ..lang("Makefile", HASH, &[], &[], &["mk", "mak"])
+    },
+    Language {
+        filenames: &["Dockerfile", "Containerfile"],
+        ..lang("Dockerfile", HASH, &[], DQ, &["dockerfile"])
+    },
+    Language { filenames: &["CMakeLists.txt"], ..lang("CMake", HASH, &[("#[[", "]]")], DQ, &["cmake"]) },
+    lang("Terraform", &["#", "//"], C_BLOCK, DQ, &["tf", "tfvars", "hcl"]),
+    lang("Protobuf", C_LINE, C_BLOCK, DQ_SQ, &["proto"]),
+    lang("Assembly", &[";", "#"], C_BLOCK, DQ_SQ, &["asm", "s"]),
+    Language { interpreters: &["awk", "gawk"], ..lang("Awk", HASH, &[], DQ, &["awk"]) },
+    lang("Batch", &["rem ", "REM ", "::"], &[], DQ, &["bat", "cmd"]),
+    lang("Vim script", &["\""], &[], &['\''], &["vim"]),
+];
+
+/// Code, comment and blank line counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineCounts {
+    pub code: u64,
+    pub comments: u64,
+    pub blanks: u64,
+}
+
+impl LineCounts {
+    pub fn lines(&self) -> u64 {
+        self.code + self.comments + self.blanks
+    }
+}
+
+impl std::ops::AddAssign for LineCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+/// Language and line counts of one source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLines {
+    pub language: String,
+    #[serde(flatten)]
+    pub counts: LineCounts,
+}
+
+/// Files and lines of one language
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageTotals {
+    pub files: usize,
+    #[serde(flatten)]
+    pub counts: LineCounts,
+}
+
+impl LanguageTotals {
+    fn add(&mut self, counts: LineCounts) {
+        self.files += 1;
+        self.counts += counts;
+    }
+}
+
+/// Line counts by language, overall and per directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlocReport {
+    /// Totals by language over the whole tree
+    #[serde(default)]
+    pub languages: BTreeMap<String, LanguageTotals>,
+    /// Totals by language for each directory, recursive
+    #[serde(default)]
+    pub directories: BTreeMap<String, BTreeMap<String, LanguageTotals>>,
+}
+
+impl SlocReport {
+    /// Count an analyzed file under `root`; archive entries are left out
+    pub fn add(&mut self, root: &Path, info: &FileInfo) {
+        let Some(sloc) = info.sloc.as_ref().filter(|_| info.archive_entry.is_none()) else {
+            return;
+        };
+        self.languages.entry(sloc.language.clone()).or_default().add(sloc.counts);
+        for dir in Path::new(&info.path).ancestors().skip(1).take_while(|d| d.starts_with(root)) {
+            self.directories
+                .entry(dir.display().to_string())
+                .or_default()
+                .entry(sloc.language.clone())
+                .or_default()
+                .add(sloc.counts);
+        }
+    }
+
+    /// Sum over all languages
+    pub fn total(&self) -> LanguageTotals {
+        let mut total = LanguageTotals::default();
+        for t in self.languages.values() {
+            total.files += t.files;
+            total.counts += t.counts;
+        }
+        total
+    }
+}
+
+/// Aggregate analyzed files under `root`
+pub fn summarize(root: &Path, files: &[FileInfo]) -> SlocReport {
+    let mut report = SlocReport::default();
+    for file in files {
+        report.add(root, file);
+    }
+    report
+}
+
+/// Language of `path` by file name, then extension, then `#!` line in `head`
+pub fn detect(path: &str, ext: Option<&str>, head: &[u8]) -> Option<&'static str> {
+    language_of(path, ext, head).map(|l| l.name)
+}
+
+fn language_of(path: &str, ext: Option<&str>, head: &[u8]) -> Option<&'static Language> {
+    let name = Path::new(path).file_name()?.to_str()?;
+    // `Dockerfile.dev` and `Makefile.am` go by their base name
+    let by_name = |f: &&str| name.strip_prefix(*f).is_some_and(|r| r.is_empty() || r.starts_with('.'));
+    if let Some(lang) = LANGUAGES.iter().find(|l| l.filenames.iter().any(by_name)) {
+        return Some(lang);
+    }
+    if let Some(lang) = ext.and_then(|ext| LANGUAGES.iter().find(|l| l.extensions.contains(&ext))) {
+        return Some(lang);
+    }
+    let interpreter = interpreter(head)?;
+    LANGUAGES.iter().find(|l| l.interpreters.contains(&interpreter.as_str()))
+}
+
+/// Interpreter named by a `#!` line (`#!/usr/bin/env python3` is `python`)
+fn interpreter(head: &[u8]) -> Option<String> {
+    let line = head.strip_prefix(b"#!")?;
+    let line = &line[..line.iter().position(|&b| b == b'\n').unwrap_or(line.len())];
+    let line = std::str::from_utf8(line).ok()?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    let trimmed = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(if trimmed.is_empty() { program } else { trimmed }.to_string())
+}
+
+/// Count the lines of `text` written in `language` (a name from [`detect`])
+pub fn count(text: &str, language: &str) -> Option<LineCounts> {
+    let lang = LANGUAGES.iter().find(|l| l.name == language)?;
+    let mut counts = LineCounts::default();
+    // Open block comment (index into `lang.block`) and its nesting depth
+    let mut block: Option<(usize, usize)> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            counts.blanks += 1;
+            continue;
+        }
+        let in_comment = block.is_some();
+        let (mut code, mut comment) = (false, in_comment);
+        let mut quote: Option<char> = None;
+        let mut rest = trimmed;
+
+        while let Some(c) = rest.chars().next() {
+            if let Some((index, depth)) = block {
+                let (open, close) = lang.block[index];
+                if rest.starts_with(close) {
+                    rest = &rest[close.len()..];
+                    block = (depth > 1).then(|| (index, depth - 1));
+                } else if lang.nested && rest.starts_with(open) {
+                    rest = &rest[open.len()..];
+                    block = Some((index, depth + 1));
+                } else {
+                    rest = &rest[c.len_utf8()..];
+                }
+                continue;
+            }
+            if let Some(q) = quote {
+                if c == '\\' {
+                    rest = &rest[c.len_utf8()..];
+                    if let Some(escaped) = rest.chars().next() {
+                        rest = &rest[escaped.len_utf8()..];
+                    }
+                    continue;
+                }
+                if c == q {
+                    quote = None;
+                }
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            // Block openers first: Lua `--[[`, Julia `#=` and CMake `#[[` start
+            // with the line marker
+            if let Some(index) = lang.block.iter().position(|(open, _)| rest.starts_with(open)) {
+                comment = true;
+                rest = &rest[lang.block[index].0.len()..];
+                block = Some((index, 1));
+                continue;
+            }
+            if lang.line.iter().any(|m| rest.starts_with(m)) {
+                comment = true;
+                break;
+            }
+            if lang.quotes.contains(&c) {
+                quote = Some(c);
+            }
+            if !c.is_whitespace() {
+                code = true;
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+
+        if code {
+            counts.code += 1;
+        } else if comment {
+            counts.comments += 1;
+        } else {
+            counts.blanks += 1;
+        }
+    }
+    Some(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("/src/main.rs", Some("rs"), b""), Some("Rust"));
+        assert_eq!(detect("/repo/Makefile", None, b""), Some("Makefile"));
+        assert_eq!(detect("/repo/Dockerfile.dev", Some("dev"), b""), Some("Dockerfile"));
+        assert_eq!(detect("/repo/CMakeLists.txt", Some("txt"), b""), Some("CMake"));
+        assert_eq!(detect("/bin/tool", None, b"#!/usr/bin/env python3\nprint()"), Some("Python"));
+        assert_eq!(detect("/bin/run", None, b"#!/bin/bash -e\n"), Some("Shell"));
+        assert_eq!(detect("/bin/node", None, b"#!/usr/bin/env -S node --harmony\n"), Some("JavaScript"));
+        assert_eq!(detect("/deploy.v2", Some("v2"), b"#!/bin/sh\n"), Some("Shell"));
+        assert_eq!(detect("/notes.txt", Some("txt"), b"hello"), None);
+        assert_eq!(detect("/data/blob", None, b"\x7fELF"), None);
+    }
+
+    #[test]
+    fn test_count_nested_and_block_comments() {
+        let rust = r#"//! Crate docs
+
+/* outer /* nested */ still comment */
+fn main() {
+    let s = "/* not a comment */"; // trailing
+    /*
+     * block
+     */
+
+    println!("{}", s); /* inline */
+}
+"#;
+        assert_eq!(
+            count(rust, "Rust"),
+            Some(LineCounts { code: 4, comments: 5, blanks: 2 })
+        );
+
+        // C block comments do not nest: the first `*/` closes
+        let c = "/* a /* b */ int x;\nint y; /* open\n still */\n";
+        assert_eq!(count(c, "C"), Some(LineCounts { code: 2, comments: 1, blanks: 0 }));
+
+        let python = "#!/usr/bin/env python\n\"\"\"Module\ndocs.\"\"\"\n\nx = '#'  # set x\n";
+        assert_eq!(count(python, "Python"), Some(LineCounts { code: 1, comments: 3, blanks: 1 }));
+
+        // Block openers that begin with the line marker
+        let lua = "--[[ header
+  more
+  more
+]]
+print('--[[') -- done
+";
+        assert_eq!(count(lua, "Lua"), Some(LineCounts { code: 1, comments: 4, blanks: 0 }));
+        let julia = "#= header
+  more
+  more
+=#
+x = 1 # one
+";
+        assert_eq!(count(julia, "Julia"), Some(LineCounts { code: 1, comments: 4, blanks: 0 }));
+        assert_eq!(count("x", "Klingon"), None);
+    }
+
+    #[test]
+    fn test_summarize_per_directory() {
+        use crate::engine::{analyze_bytes, EngineOptions};
+
+        let options = EngineOptions::default();
+        let files = [
+            analyze_bytes(b"fn a() {}\n// note\n", "/repo/src/a.rs", &options),
+            analyze_bytes(b"fn b() {}\n\n", "/repo/src/util/b.rs", &options),
+            analyze_bytes(b"#!/bin/sh\necho hi\n", "/repo/run", &options),
+            analyze_bytes(b"plain words\n", "/repo/README.txt", &options),
+        ];
+        let report = summarize(Path::new("/repo"), &files);
+
+        assert_eq!(report.languages["Rust"], LanguageTotals {
+            files: 2,
+            counts: LineCounts { code: 2, comments: 1, blanks: 1 },
+        });
+        assert_eq!(report.languages["Shell"].counts, LineCounts { code: 1, comments: 1, blanks: 0 });
+        assert_eq!(report.directories["/repo"].len(), 2);
+        assert_eq!(report.directories["/repo/src"]["Rust"].files, 2);
+        assert_eq!(report.directories["/repo/src/util"]["Rust"].counts.blanks, 1);
+        assert!(!report.directories.contains_key("/"));
+        assert_eq!(report.total().counts.lines(), 6);
+    }
+}
@@ -0,0 +1,417 @@
+//! Content Sniffing Module
+//!
+//! Magic-byte file type detection. Looks at the first [`SNIFF_LEN`] bytes of a
+//! file and reports a MIME type with a confidence score, and whether the file
+//! extension disagrees with what the content says it is.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes of file header needed for detection (also the binary-check window)
+pub const SNIFF_LEN: usize = 8192;
+
+const MIME_OCTET_STREAM: &str = "application/octet-stream";
+const MIME_TEXT: &str = "text/plain";
+
+/// Weak signatures (a bare `MZ`) are reported but never flag a mismatch
+const MISMATCH_MIN_CONFIDENCE: f32 = 0.8;
+
+/// Detected content type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentType {
+    pub mime_type: String,
+    /// 0.0 (guess) to 1.0 (exact signature match)
+    pub confidence: f32,
+    /// The extension names a different format than the content
+    pub extension_mismatch: bool,
+    /// Canonical extension of the detected format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<Cow<'static, str>>,
+}
+
+impl Default for ContentType {
+    fn default() -> Self {
+        Self {
+            mime_type: MIME_OCTET_STREAM.to_string(),
+            confidence: 0.0,
+            extension_mismatch: false,
+            kind: None,
+        }
+    }
+}
+
+/// A format recognised from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Format {
+    mime: &'static str,
+    kind: &'static str,
+    confidence: f32,
+    /// Extensions that legitimately carry this content
+    extensions: &'static [&'static str],
+}
+
+const ELF: Format = Format {
+    mime: "application/x-elf",
+    kind: "elf",
+    confidence: 1.0,
+    extensions: &["so", "o", "ko", "elf", "bin", "axf", "prx", "out", "debug"],
+};
+const PE: Format = Format {
+    mime: "application/vnd.microsoft.portable-executable",
+    kind: "exe",
+    confidence: 0.95,
+    extensions: &["exe", "dll", "sys", "scr", "ocx", "cpl", "efi", "drv", "mui", "com"],
+};
+const MZ: Format = Format { confidence: 0.5, ..PE };
+const PDF: Format = Format {
+    mime: "application/pdf",
+    kind: "pdf",
+    confidence: 1.0,
+    extensions: &["pdf", "ai"],
+};
+const PNG: Format = Format {
+    mime: "image/png",
+    kind: "png",
+    confidence: 1.0,
+    extensions: &["png", "apng"],
+};
+const JPEG: Format = Format {
+    mime: "image/jpeg",
+    kind: "jpg",
+    confidence: 0.9,
+    extensions: &["jpg", "jpeg", "jpe", "jfif"],
+};
+const ZIP: Format = Format {
+    mime: "application/zip",
+    kind: "zip",
+    confidence: 0.9,
+    extensions: &[
+        "zip", "jar", "war", "ear", "apk", "aab", "ipa", "xpi", "whl", "nupkg", "vsix", "epub",
+        "docx", "docm", "xlsx", "xlsm", "pptx", "pptm", "odt", "ods", "odp", "kmz", "cbz",
+    ],
+};
+const JAR: Format = Format {
+    mime: "application/java-archive",
+    kind: "jar",
+    confidence: 0.95,
+    extensions: &["jar", "war", "ear", "apk", "aab"],
+};
+const DOCX: Format = Format {
+    mime: "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    kind: "docx",
+    confidence: 0.95,
+    extensions: &["docx", "docm", "dotx", "dotm"],
+};
+const XLSX: Format = Format {
+    mime: "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    kind: "xlsx",
+    confidence: 0.95,
+    extensions: &["xlsx", "xlsm", "xltx", "xltm"],
+};
+const PPTX: Format = Format {
+    mime: "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    kind: "pptx",
+    confidence: 0.95,
+    extensions: &["pptx", "pptm", "potx", "potm", "ppsx"],
+};
+const GZIP: Format = Format {
+    mime: "application/gzip",
+    kind: "gz",
+    confidence: 0.95,
+    extensions: &["gz", "tgz", "gzip", "svgz", "emz"],
+};
+const TAR: Format = Format {
+    mime: "application/x-tar",
+    kind: "tar",
+    confidence: 0.95,
+    extensions: &["tar", "ova"],
+};
+const SQLITE: Format = Format {
+    mime: "application/vnd.sqlite3",
+    kind: "sqlite",
+    confidence: 1.0,
+    extensions: &["sqlite", "sqlite3", "db", "db3", "s3db", "sl3", "gpkg", "mbtiles"],
+};
+
+/// Extensions that reliably promise a magic-detectable format.
+/// A file carrying one of these whose content does not match gets flagged.
+const STRONG_EXTENSIONS: &[&str] = &[
+    "pdf", "png", "jpg", "jpeg", "zip", "jar", "docx", "xlsx", "pptx", "gz", "tgz", "sqlite",
+    "sqlite3", "exe", "dll",
+];
+
+/// Detect the content type of a file from its leading bytes
+pub fn sniff(head: &[u8], extension: Option<&str>) -> ContentType {
+    let extension = extension.map(|e| e.to_ascii_lowercase());
+    let ext = extension.as_deref().filter(|e| !e.is_empty());
+
+    if let Some(format) = detect_format(head) {
+        let extension_mismatch = format.confidence >= MISMATCH_MIN_CONFIDENCE
+            && ext.is_some_and(|e| !format.extensions.contains(&e));
+        return ContentType {
+            mime_type: format.mime.to_string(),
+            confidence: format.confidence,
+            extension_mismatch,
+            kind: Some(Cow::Borrowed(format.kind)),
+        };
+    }
+
+    if let Some(script) = detect_script(head) {
+        let extension_mismatch = ext.is_some_and(|e| {
+            !script.extensions.contains(&e) && is_strong_extension(e)
+        });
+        return ContentType {
+            mime_type: script.mime.to_string(),
+            confidence: script.confidence,
+            extension_mismatch,
+            kind: Some(Cow::Borrowed(script.kind)),
+        };
+    }
+
+    // No signature: a strongly-typed extension on non-empty content is suspicious
+    let extension_mismatch = !head.is_empty() && ext.is_some_and(is_strong_extension);
+
+    if head.is_empty() {
+        ContentType { extension_mismatch, ..ContentType::default() }
+    } else if looks_like_text(head) {
+        ContentType {
+            mime_type: MIME_TEXT.to_string(),
+            confidence: 0.5,
+            extension_mismatch,
+            kind: None,
+        }
+    } else {
+        ContentType {
+            mime_type: MIME_OCTET_STREAM.to_string(),
+            confidence: 0.2,
+            extension_mismatch,
+            kind: None,
+        }
+    }
+}
+
+/// Heuristic binary check shared by every analysis path: a NUL in the header
+pub fn is_binary(head: &[u8]) -> bool {
+    head.iter().take(SNIFF_LEN).any(|&b| b == 0)
+}
+
+fn is_strong_extension(ext: &str) -> bool {
+    STRONG_EXTENSIONS.contains(&ext)
+}
+
+fn looks_like_text(head: &[u8]) -> bool {
+    if is_binary(head) {
+        return false;
+    }
+    // Tolerate a multi-byte sequence cut off at the end of the window
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn detect_format(head: &[u8]) -> Option<Format> {
+    if head.starts_with(b"\x7fELF") {
+        return Some(ELF);
+    }
+    if head.starts_with(b"MZ") {
+        return Some(if has_pe_header(head) { PE } else { MZ });
+    }
+    if head.starts_with(b"%PDF-") {
+        return Some(PDF);
+    }
+    if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(PNG);
+    }
+    if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(JPEG);
+    }
+    if head.starts_with(b"PK\x03\x04") {
+        return Some(detect_zip_flavor(head));
+    }
+    if head.starts_with(b"PK\x05\x06") {
+        return Some(ZIP);
+    }
+    if head.starts_with(&[0x1F, 0x8B, 0x08]) {
+        return Some(GZIP);
+    }
+    if head.starts_with(b"SQLite format 3\0") {
+        return Some(SQLITE);
+    }
+    if head.len() >= 262 && &head[257..262] == b"ustar" {
+        return Some(TAR);
+    }
+    None
+}
+
+/// `MZ` stub whose e_lfanew points at a `PE\0\0` signature
+fn has_pe_header(head: &[u8]) -> bool {
+    let Some(lfanew) = head.get(0x3C..0x40) else {
+        return false;
+    };
+    let offset = u32::from_le_bytes([lfanew[0], lfanew[1], lfanew[2], lfanew[3]]) as usize;
+    // e_lfanew comes from the file; `offset + 4` can wrap a 32-bit usize
+    let Some(end) = offset.checked_add(4) else {
+        return false;
+    };
+    head.get(offset..end) == Some(b"PE\0\0")
+}
+
+/// Tell JAR and OOXML containers apart from plain ZIP archives
+fn detect_zip_flavor(head: &[u8]) -> Format {
+    let first_entry = head
+        .get(26..28)
+        .map(|n| u16::from_le_bytes([n[0], n[1]]) as usize)
+        .and_then(|len| head.get(30..30 + len));
+
+    if let Some(name) = first_entry {
+        if name.starts_with(b"META-INF/") {
+            return JAR;
+        }
+    }
+
+    // OOXML packages list [Content_Types].xml and part folders near the start
+    let window = &head[..head.len().min(SNIFF_LEN)];
+    if contains(window, b"[Content_Types].xml") || contains(window, b"_rels/.rels") {
+        if contains(window, b"word/") {
+            return DOCX;
+        }
+        if contains(window, b"xl/") {
+            return XLSX;
+        }
+        if contains(window, b"ppt/") {
+            return PPTX;
+        }
+    }
+
+    ZIP
+}
+
+fn detect_script(head: &[u8]) -> Option<Format> {
+    let rest = head.strip_prefix(b"#!")?;
+    let line_end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+    let line = String::from_utf8_lossy(&rest[..line_end]);
+    let mut parts = line.split_whitespace();
+    let program = parts.next()?;
+    let mut interpreter = program.rsplit('/').next().unwrap_or(program);
+    if interpreter == "env" {
+        interpreter = parts.find(|p| !p.starts_with('-')).unwrap_or(interpreter);
+    }
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    let (mime, kind, extensions): (_, _, &'static [&'static str]) = match interpreter {
+        "sh" | "bash" | "dash" | "zsh" | "ksh" | "ash" => {
+            ("text/x-shellscript", "sh", &["sh", "bash", "zsh", "ksh", "command"])
+        }
+        "python" | "pypy" => ("text/x-python", "py", &["py", "pyw"]),
+        "perl" => ("text/x-perl", "pl", &["pl", "pm", "cgi", "t"]),
+        "ruby" => ("text/x-ruby", "rb", &["rb"]),
+        "node" | "nodejs" | "deno" | "bun" => ("text/javascript", "js", &["js", "mjs", "cjs", "ts"]),
+        "php" => ("application/x-httpd-php", "php", &["php"]),
+        "lua" => ("text/x-lua", "lua", &["lua"]),
+        _ => ("text/x-script", "script", &[]),
+    };
+    let confidence = if extensions.is_empty() { 0.6 } else { 0.9 };
+
+    Some(Format { mime, kind, confidence, extensions })
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_signatures() {
+        assert_eq!(sniff(b"\x7fELF\x02\x01\x01", None).mime_type, "application/x-elf");
+        assert_eq!(sniff(b"%PDF-1.7\n", Some("pdf")).mime_type, "application/pdf");
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", Some("png")).mime_type, "image/png");
+        assert_eq!(sniff(&[0xFF, 0xD8, 0xFF, 0xE0], Some("jpg")).mime_type, "image/jpeg");
+        assert_eq!(sniff(&[0x1F, 0x8B, 0x08, 0x00], Some("gz")).mime_type, "application/gzip");
+        assert_eq!(sniff(b"SQLite format 3\0", Some("db")).mime_type, "application/vnd.sqlite3");
+    }
+
+    #[test]
+    fn test_pe_header() {
+        let mut exe = vec![0u8; 0x88];
+        exe[..2].copy_from_slice(b"MZ");
+        exe[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        exe[0x80..0x84].copy_from_slice(b"PE\0\0");
+
+        let detected = sniff(&exe, Some("dll"));
+        assert_eq!(detected.mime_type, PE.mime);
+        assert_eq!(detected.confidence, 0.95);
+        assert!(!detected.extension_mismatch);
+
+        let weak = sniff(b"MZ not really", Some("txt"));
+        assert_eq!(weak.confidence, 0.5);
+        assert!(!weak.extension_mismatch);
+
+        exe[0x3C..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(!has_pe_header(&exe));
+    }
+
+    #[test]
+    fn test_tar_ustar_magic() {
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(sniff(&tar, Some("tar")).mime_type, "application/x-tar");
+    }
+
+    #[test]
+    fn test_zip_flavors() {
+        let mut docx = b"PK\x03\x04".to_vec();
+        docx.resize(26, 0);
+        docx.extend_from_slice(&19u16.to_le_bytes());
+        docx.extend_from_slice(&[0, 0]);
+        docx.extend_from_slice(b"[Content_Types].xml");
+        docx.extend_from_slice(b"....word/document.xml");
+        assert_eq!(sniff(&docx, Some("docx")).kind.as_deref(), Some("docx"));
+
+        let mut jar = b"PK\x03\x04".to_vec();
+        jar.resize(26, 0);
+        jar.extend_from_slice(&9u16.to_le_bytes());
+        jar.extend_from_slice(&[0, 0]);
+        jar.extend_from_slice(b"META-INF/");
+        assert_eq!(sniff(&jar, Some("jar")).mime_type, "application/java-archive");
+    }
+
+    #[test]
+    fn test_shebang_scripts() {
+        let py = sniff(b"#!/usr/bin/env python3\nprint('hi')\n", None);
+        assert_eq!(py.mime_type, "text/x-python");
+        assert_eq!(py.kind.as_deref(), Some("py"));
+
+        let sh = sniff(b"#!/bin/bash -e\necho hi\n", Some("sh"));
+        assert_eq!(sh.mime_type, "text/x-shellscript");
+        assert!(!sh.extension_mismatch);
+    }
+
+    #[test]
+    fn test_extension_mismatch() {
+        // ELF binary disguised as a text file
+        assert!(sniff(b"\x7fELF\x02\x01\x01", Some("txt")).extension_mismatch);
+        // PDF extension on plain text
+        assert!(sniff(b"just some words", Some("PDF")).extension_mismatch);
+        // Ambiguous extensions and missing extensions are not flagged
+        assert!(!sniff(b"just some words", Some("db")).extension_mismatch);
+        assert!(!sniff(b"\x7fELF\x02\x01\x01", None).extension_mismatch);
+        assert!(!sniff(b"", Some("pdf")).extension_mismatch);
+    }
+
+    #[test]
+    fn test_text_and_binary_fallback() {
+        let text = sniff("héllo wörld".as_bytes(), Some("txt"));
+        assert_eq!(text.mime_type, "text/plain");
+        assert!(!text.extension_mismatch);
+
+        let bin = sniff(&[0x00, 0x13, 0x37, 0x00], None);
+        assert_eq!(bin.mime_type, "application/octet-stream");
+        assert!(is_binary(&[0x41, 0x00]));
+    }
+}
@@ -0,0 +1,79 @@
+//! Storage Module
+//!
+//! Where a file's bytes live on disk: device and inode (the identity shared
+//! by hard links), link count, allocated blocks and whether the file is
+//! sparse. Apparent length and allocated space diverge for sparse VM images
+//! and on compressing filesystems, which matters when sizing copies.
+
+use serde::{Deserialize, Serialize};
+
+/// `st_blocks` unit
+const BLOCK_SIZE: u64 = 512;
+
+/// On-disk identity and allocation of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageInfo {
+    pub dev: u64,
+    pub inode: u64,
+    /// Hard links to the inode, this path included
+    pub links: u64,
+    /// Allocated 512-byte blocks
+    pub blocks: u64,
+    /// Allocated bytes (`blocks * 512`)
+    pub allocated: u64,
+    /// At least one filesystem block fewer allocated than the file's length
+    /// (holes, or filesystem compression)
+    pub sparse: bool,
+}
+
+impl StorageInfo {
+    pub fn new(dev: u64, inode: u64, links: u64, blocks: u64, size: u64, fs_block: u64) -> Self {
+        let allocated = blocks.saturating_mul(BLOCK_SIZE);
+        Self {
+            dev,
+            inode,
+            links,
+            blocks,
+            allocated,
+            sparse: size > 0 && size.saturating_sub(allocated) >= fs_block.max(BLOCK_SIZE),
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Self::new(
+            metadata.dev(),
+            metadata.ino(),
+            metadata.nlink(),
+            metadata.blocks(),
+            metadata.len(),
+            metadata.blksize(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_sparse_detection() {
+        // 1 MiB apparent with nothing written: a hole on any filesystem with holes
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("disk.img");
+        File::create(&path).unwrap().set_len(1024 * 1024).unwrap();
+        let hole = StorageInfo::from_metadata(&fs::metadata(&path).unwrap());
+        assert_eq!(hole.links, 1);
+        assert!(hole.allocated < 1024 * 1024);
+        assert!(hole.sparse);
+
+        let dense = StorageInfo::new(1, 2, 1, 8, 4096, 4096);
+        assert_eq!(dense.allocated, 4096);
+        assert!(!dense.sparse);
+        // A partly filled last block is not a hole
+        assert!(!StorageInfo::new(1, 2, 1, 8, 5000, 4096).sparse);
+        assert!(!StorageInfo::new(1, 2, 1, 0, 0, 4096).sparse);
+    }
+}
@@ -0,0 +1,189 @@
+//! Symlink Safety Module
+//!
+//! Every symbolic link met during the walk is recorded with its target and
+//! classified before the walker decides whether to follow it: dangling links
+//! and loops cannot be followed, and links escaping the source root are never
+//! followed while the source is held under a read-only lock.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Prefixes of the host's own files; a link into them leaks system content
+const SYSTEM_PATHS: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/root", "/run",
+    "/sbin", "/sys", "/usr", "/var",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkClass {
+    /// Resolves to a path inside the source root
+    Internal,
+    /// Target does not exist
+    Dangling,
+    /// Resolves to one of its own ancestors, or never resolves (`ELOOP`)
+    Loop,
+    /// Resolves outside the source root
+    Escaping,
+    /// Resolves outside the source root into system paths (`/etc`, `/proc`...)
+    System,
+}
+
+impl LinkClass {
+    /// Resolves outside the source root
+    pub fn escapes(self) -> bool {
+        matches!(self, LinkClass::Escaping | LinkClass::System)
+    }
+
+    /// Whether the walker may follow a link of this class
+    pub fn followable(self, ro_locked: bool) -> bool {
+        match self {
+            LinkClass::Dangling | LinkClass::Loop => false,
+            class => !(ro_locked && class.escapes()),
+        }
+    }
+}
+
+/// A symbolic link found in the source tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymlinkRecord {
+    pub path: String,
+    /// Link contents as stored
+    pub target: String,
+    /// Fully resolved target; `None` when dangling or looping
+    #[serde(default)]
+    pub resolved: Option<String>,
+    pub class: LinkClass,
+    /// Whether the walker followed it
+    #[serde(default)]
+    pub followed: bool,
+}
+
+/// Read and classify the link at `path`; `root` must be canonical
+pub fn inspect(path: &Path, root: &Path) -> SymlinkRecord {
+    let target = fs::read_link(path)
+        .map(|t| t.display().to_string())
+        .unwrap_or_default();
+
+    let (resolved, class) = match fs::canonicalize(path) {
+        Ok(resolved) => {
+            // A directory link to one of its own ancestors recurses forever
+            let parent = path.parent().and_then(|p| fs::canonicalize(p).ok());
+            let class = if resolved.is_dir() && parent.is_some_and(|p| p.starts_with(&resolved)) {
+                LinkClass::Loop
+            } else if resolved.starts_with(root) {
+                LinkClass::Internal
+            } else if SYSTEM_PATHS.iter().any(|s| resolved.starts_with(s)) {
+                LinkClass::System
+            } else {
+                LinkClass::Escaping
+            };
+            (Some(resolved.display().to_string()), class)
+        }
+        Err(e) if is_loop(&e) => (None, LinkClass::Loop),
+        Err(_) => (None, LinkClass::Dangling),
+    };
+
+    SymlinkRecord {
+        path: path.display().to_string(),
+        target,
+        resolved,
+        class,
+        followed: false,
+    }
+}
+
+fn is_loop(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::ELOOP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer;
+    use crate::config::Config;
+    use std::os::unix::fs::symlink;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_classification() {
+        let temp = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs/a.txt"), "a").unwrap();
+        fs::write(outside.path().join("secret.txt"), "s").unwrap();
+
+        symlink("docs/a.txt", root.join("internal")).unwrap();
+        symlink("missing.txt", root.join("dangling")).unwrap();
+        symlink("..", root.join("docs/up")).unwrap();
+        symlink("self", root.join("self")).unwrap();
+        symlink(outside.path().join("secret.txt"), root.join("escape")).unwrap();
+        symlink("/etc", root.join("etc")).unwrap();
+
+        let class = |name: &str| inspect(&root.join(name), &root).class;
+        assert_eq!(class("internal"), LinkClass::Internal);
+        assert_eq!(class("dangling"), LinkClass::Dangling);
+        assert_eq!(class("docs/up"), LinkClass::Loop);
+        assert_eq!(class("self"), LinkClass::Loop);
+        assert_eq!(class("escape"), LinkClass::Escaping);
+        assert_eq!(class("etc"), LinkClass::System);
+
+        let internal = inspect(&root.join("internal"), &root);
+        assert_eq!(internal.target, "docs/a.txt");
+        assert_eq!(internal.resolved, Some(root.join("docs/a.txt").display().to_string()));
+
+        assert!(LinkClass::Escaping.followable(false));
+        assert!(!LinkClass::System.followable(true));
+        assert!(LinkClass::Internal.followable(true));
+    }
+
+    #[tokio::test]
+    async fn test_walk_records_and_confines_links() {
+        let temp = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        let root = temp.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(outside.path().join("secret.txt"), "s").unwrap();
+        symlink("a.txt", root.join("alias")).unwrap();
+        symlink("nowhere", root.join("broken")).unwrap();
+        symlink(".", root.join("loop")).unwrap();
+        symlink(outside.path(), root.join("shared")).unwrap();
+
+        let source = root.to_str().unwrap();
+        let analyzed = |results: &analyzer::AnalysisResults| -> Vec<String> {
+            results.files.iter()
+                .map(|f| f.path.strip_prefix(source).unwrap().to_string())
+                .collect()
+        };
+
+        let mut config = Config::default();
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        assert_eq!(analyzed(&results), ["/a.txt", "/alias", "/shared/secret.txt"]);
+        let links: Vec<_> = results.symlinks.iter()
+            .map(|l| (l.path.strip_prefix(source).unwrap(), l.class, l.followed))
+            .collect();
+        assert_eq!(links, [
+            ("/alias", LinkClass::Internal, true),
+            ("/broken", LinkClass::Dangling, false),
+            ("/loop", LinkClass::Loop, false),
+            ("/shared", LinkClass::Escaping, true),
+        ]);
+
+        // Under a read-only lock the escaping link is recorded but never entered
+        config.analysis.ro_locked = true;
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        assert_eq!(analyzed(&results), ["/a.txt", "/alias"]);
+        assert!(!results.symlinks[3].followed);
+
+        // Not following links at all still records them
+        config.analysis.follow_symlinks = false;
+        let results = analyzer::analyze(source, &config).await.unwrap();
+        assert_eq!(analyzed(&results), ["/a.txt"]);
+        assert_eq!(results.symlinks.len(), 4);
+        assert!(results.symlinks.iter().all(|l| !l.followed));
+    }
+}
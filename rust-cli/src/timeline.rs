@@ -0,0 +1,212 @@
+//! Timeline Module
+//!
+//! MAC(B) timelines from analyzed files for incident response: a Sleuth Kit
+//! bodyfile for `mactime` and other timeline tools, or a CSV in `mactime -d`
+//! layout, one row per distinct timestamp of a file, sorted by time.
+
+use std::fmt::Write as _;
+
+use anyhow::Result;
+use tracing::info;
+
+use diamond_drill::engine::FileInfo;
+use diamond_drill::timestamps;
+
+/// Output format for `diamond timeline` and `[export] format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TimelineFormat {
+    /// Sleuth Kit bodyfile (`MD5|name|inode|mode|UID|GID|size|atime|mtime|ctime|crtime`)
+    Bodyfile,
+    /// Time-sorted CSV with one row per timestamp and its `macb` flags
+    Csv,
+}
+
+impl TimelineFormat {
+    /// The timeline an `[export] format` value asks for, if any
+    pub fn from_export(format: &str) -> Option<Self> {
+        clap::ValueEnum::from_str(format, true).ok()
+    }
+}
+
+/// Render the timeline of `files`; files without any recorded time are left out
+pub fn render(files: &[FileInfo], format: TimelineFormat) -> String {
+    match format {
+        TimelineFormat::Bodyfile => bodyfile(files),
+        TimelineFormat::Csv => csv(files),
+    }
+}
+
+/// Write the timeline to `dest` (`-` writes to stdout)
+pub async fn export(files: &[FileInfo], format: TimelineFormat, dest: &str) -> Result<()> {
+    let content = render(files, format);
+    if dest == "-" {
+        print!("{}", content);
+        return Ok(());
+    }
+    tokio::fs::write(dest, content).await?;
+    info!("🕒 Timeline exported to: {}", dest);
+    Ok(())
+}
+
+/// The four times of a file in `m`, `a`, `c`, `b` order
+fn times(file: &FileInfo) -> [Option<&str>; 4] {
+    [&file.modified, &file.accessed, &file.changed, &file.created].map(|t| t.as_deref())
+}
+
+fn bodyfile(files: &[FileInfo]) -> String {
+    let mut out = String::new();
+    for file in files {
+        let [m, a, c, b] = times(file).map(|t| t.and_then(timestamps::parse_unix));
+        if [m, a, c, b].iter().all(Option::is_none) {
+            continue;
+        }
+        let (uid, gid) = file.ownership.as_ref().map_or((0, 0), |o| (o.uid, o.gid));
+        let _ = writeln!(
+            out,
+            "0|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+            file.path,
+            inode_of(file),
+            mode_string(file),
+            uid,
+            gid,
+            file.size,
+            a.unwrap_or(0),
+            m.unwrap_or(0),
+            c.unwrap_or(0),
+            b.unwrap_or(0),
+        );
+    }
+    out
+}
+
+fn csv(files: &[FileInfo]) -> String {
+    // (unix seconds, timestamp, macb flags, file)
+    let mut events: Vec<(i64, &str, String, &FileInfo)> = Vec::new();
+    for file in files {
+        let stamps = times(file);
+        let mut seen: Vec<&str> = Vec::new();
+        for time in stamps.iter().flatten() {
+            if seen.contains(time) {
+                continue;
+            }
+            seen.push(time);
+            let Some(secs) = timestamps::parse_unix(time) else {
+                continue;
+            };
+            let flags = stamps.iter()
+                .zip("macb".chars())
+                .map(|(t, flag)| if *t == Some(*time) { flag } else { '.' })
+                .collect();
+            events.push((secs, time, flags, file));
+        }
+    }
+    events.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| a.1.cmp(b.1))
+            .then_with(|| a.3.path.cmp(&b.3.path))
+    });
+
+    let mut out = String::from("Date,Size,Type,Mode,UID,GID,Meta,File Name\n");
+    for (_, time, flags, file) in events {
+        let (uid, gid) = file.ownership.as_ref().map_or((0, 0), |o| (o.uid, o.gid));
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            time,
+            file.size,
+            flags,
+            mode_string(file),
+            uid,
+            gid,
+            inode_of(file),
+            csv_field(&file.path)
+        );
+    }
+    out
+}
+
+fn inode_of(file: &FileInfo) -> u64 {
+    file.storage.as_ref().map_or(0, |s| s.inode)
+}
+
+/// `fls`-style mode: `r/r` or `d/d`, then `ls -l` permission letters
+fn mode_string(file: &FileInfo) -> String {
+    let bits = file.ownership.as_ref()
+        .map(|o| o.mode.as_str())
+        .or(file.permissions.as_deref())
+        .and_then(|m| u32::from_str_radix(m, 8).ok())
+        .unwrap_or(0);
+    let kind = if file.file_type == "directory" { "d/d" } else { "r/r" };
+
+    let mut mode = String::from(kind);
+    for (shift, special, set, unset) in [(6, 0o4000, 's', 'S'), (3, 0o2000, 's', 'S'), (0, 0o1000, 't', 'T')] {
+        let rwx = bits >> shift & 0o7;
+        mode.push(if rwx & 4 != 0 { 'r' } else { '-' });
+        mode.push(if rwx & 2 != 0 { 'w' } else { '-' });
+        mode.push(match (bits & special != 0, rwx & 1 != 0) {
+            (true, true) => set,
+            (true, false) => unset,
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    mode
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diamond_drill::engine::{analyze_bytes, EngineOptions};
+    use diamond_drill::ownership::Ownership;
+
+    fn file(path: &str, mode: u32, times: [Option<&str>; 4]) -> FileInfo {
+        let mut file = analyze_bytes(b"hello", path, &EngineOptions::default());
+        file.ownership = Some(Ownership::new(1000, 100, mode));
+        let [m, a, c, b] = times.map(|t| t.map(str::to_string));
+        (file.modified, file.accessed, file.changed, file.created) = (m, a, c, b);
+        file
+    }
+
+    #[test]
+    fn test_bodyfile_and_csv() {
+        let files = [
+            file("/case/b,1.txt", 0o100644, [
+                Some("2024-05-01T12:00:00.000000000Z"),
+                Some("2024-05-02T08:00:00.000000000Z"),
+                Some("2024-05-01T12:00:00.000000000Z"),
+                None,
+            ]),
+            file("/case/run", 0o104750, [
+                Some("2024-04-30T00:00:00.500000000Z"),
+                Some("2024-04-30T00:00:00.500000000Z"),
+                Some("2024-04-30T00:00:00.500000000Z"),
+                Some("2024-04-30T00:00:00.500000000Z"),
+            ]),
+            file("/case/untimed", 0o100644, [None; 4]),
+        ];
+
+        assert_eq!(render(&files, TimelineFormat::Bodyfile), concat!(
+            "0|/case/b,1.txt|0|r/rrw-r--r--|1000|100|5|1714636800|1714564800|1714564800|0\n",
+            "0|/case/run|0|r/rrwsr-x---|1000|100|5|1714435200|1714435200|1714435200|1714435200\n",
+        ));
+        assert_eq!(render(&files, TimelineFormat::Csv), concat!(
+            "Date,Size,Type,Mode,UID,GID,Meta,File Name\n",
+            "2024-04-30T00:00:00.500000000Z,5,macb,r/rrwsr-x---,1000,100,0,/case/run\n",
+            "2024-05-01T12:00:00.000000000Z,5,m.c.,r/rrw-r--r--,1000,100,0,\"/case/b,1.txt\"\n",
+            "2024-05-02T08:00:00.000000000Z,5,.a..,r/rrw-r--r--,1000,100,0,\"/case/b,1.txt\"\n",
+        ));
+
+        assert_eq!(TimelineFormat::from_export("bodyfile"), Some(TimelineFormat::Bodyfile));
+        assert_eq!(TimelineFormat::from_export("CSV"), Some(TimelineFormat::Csv));
+        assert_eq!(TimelineFormat::from_export("json"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Timestamps Module
+//!
+//! File times as RFC 3339 UTC strings with nanoseconds
+//! (`2024-05-01T12:00:00.250000000Z`), fixed-width so they sort as text,
+//! and parsed back into Unix seconds for timeline exports. Civil date
+//! conversion is done by hand to stay free of a date-time dependency.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// RFC 3339 form of a system time, including times before 1970
+pub fn rfc3339(time: SystemTime) -> String {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => from_unix(d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => {
+            let d = e.duration();
+            match d.subsec_nanos() {
+                0 => from_unix(-(d.as_secs() as i64), 0),
+                n => from_unix(-(d.as_secs() as i64) - 1, 1_000_000_000 - n),
+            }
+        }
+    }
+}
+
+/// RFC 3339 form of `secs` seconds and `nanos` nanoseconds since the epoch
+pub fn from_unix(secs: i64, nanos: u32) -> String {
+    let days = secs.div_euclid(SECS_PER_DAY);
+    let rem = secs.rem_euclid(SECS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        nanos
+    )
+}
+
+/// Unix seconds of an RFC 3339 timestamp (`Z` or `±hh:mm` offset,
+/// optional fraction, which is truncated)
+pub fn parse_unix(text: &str) -> Option<i64> {
+    let b = text.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ')
+        || b[13] != b':' || b[16] != b':'
+    {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| digits(text.get(range)?);
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut rest = &text[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        rest = &fraction[digits..];
+    }
+    let offset = match rest.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let minutes = digits(&rest[1..3])? * 60 + digits(&rest[4..6])?;
+            if *sign == b'-' { -minutes * 60 } else { minutes * 60 }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    Some(days * SECS_PER_DAY + hour * 3600 + minute * 60 + second - offset)
+}
+
+fn digits(s: &str) -> Option<i64> {
+    s.bytes().all(|c| c.is_ascii_digit()).then(|| s.parse().ok())?
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if month <= 2 { era * 400 + yoe + 1 } else { era * 400 + yoe }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(from_unix(0, 0), "1970-01-01T00:00:00.000000000Z");
+        assert_eq!(from_unix(951_782_400, 5), "2000-02-29T00:00:00.000000005Z");
+        assert_eq!(from_unix(1_714_564_800, 250_000_000), "2024-05-01T12:00:00.250000000Z");
+
+        let before = UNIX_EPOCH - Duration::from_millis(1500);
+        assert_eq!(rfc3339(before), "1969-12-31T23:59:58.500000000Z");
+        assert_eq!(parse_unix(&rfc3339(before)), Some(-2));
+
+        for secs in [-86_401, 0, 951_782_400, 1_714_564_800, 4_102_444_800] {
+            assert_eq!(parse_unix(&from_unix(secs, 123)), Some(secs));
+        }
+    }
+
+    #[test]
+    fn test_parse_offsets() {
+        assert_eq!(parse_unix("2024-05-01T12:00:00Z"), Some(1_714_564_800));
+        assert_eq!(parse_unix("2024-05-01T14:30:00+02:30"), Some(1_714_564_800));
+        assert_eq!(parse_unix("2024-05-01T07:00:00.9-05:00"), Some(1_714_564_800));
+        assert_eq!(parse_unix("SystemTime { tv_sec: 1, tv_nsec: 0 }"), None);
+        assert_eq!(parse_unix("2024-13-01T00:00:00Z"), None);
+        assert_eq!(parse_unix("2024-05-01T12:00:00"), None);
+    }
+}
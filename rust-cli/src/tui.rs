@@ -1,12 +1,13 @@
 //! TUI Module - Terminal User Interface with ratatui
 //!
 //! Features: vim navigation, help overlay, file preview, search filter, themes,
-//! bookmarks, virtual scrolling, async preview loading.
+//! bookmarks, virtual scrolling, async preview loading, disk usage results view.
 
 use std::io::{stdout, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::{self, File};
 use std::time::{Duration, Instant};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use anyhow::Result;
@@ -20,9 +21,14 @@ use ratatui::{
     widgets::*,
 };
 
+use diamond_drill::encoding;
+use diamond_drill::sniff;
+
 use crate::config::Config;
 use crate::analyzer;
+use crate::disk_usage::UsageReport;
 use crate::ro_lock::ReadOnlyLock;
+use crate::secrets;
 
 /// Maximum bytes to read for file preview
 const PREVIEW_MAX_BYTES: usize = 4096;
@@ -34,6 +40,8 @@ const LAZY_LOAD_LIMIT: usize = 1000;
 const MAX_BOOKMARKS: usize = 9;
 /// Preview debounce delay in milliseconds
 const PREVIEW_DEBOUNCE_MS: u64 = 100;
+/// Widest histogram bar in the results view
+const HISTOGRAM_WIDTH: usize = 24;
 
 /// Color themes
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -109,16 +117,24 @@ enum FileFilter {
 }
 
 impl FileFilter {
-    fn matches(&self, path: &PathBuf) -> bool {
+    /// `sniffed` caches the detected kind of extensionless files so that
+    /// re-filtering on every keystroke does not re-read them.
+    fn matches(&self, path: &Path, sniffed: &mut HashMap<PathBuf, Option<Cow<'static, str>>>) -> bool {
         match self {
             FileFilter::All => true,
             FileFilter::Extension(ext) => {
                 if path.is_dir() {
                     return true;
                 }
-                path.extension()
-                    .map(|e| e.to_string_lossy().to_lowercase() == ext.to_lowercase())
-                    .unwrap_or(false)
+                match path.extension() {
+                    Some(e) => e.to_string_lossy().to_lowercase() == ext.to_lowercase(),
+                    // Extensionless files match on their sniffed format
+                    None => sniffed
+                        .entry(path.to_path_buf())
+                        .or_insert_with(|| sniff_path(path).and_then(|t| t.kind))
+                        .as_deref()
+                        .is_some_and(|kind| kind.eq_ignore_ascii_case(ext)),
+                }
             }
         }
     }
@@ -186,6 +202,9 @@ struct App {
     // v8 Async preview
     last_selection_change: Instant,
     preview_path: Option<PathBuf>,
+    // v9 Results view
+    usage_view: Option<String>,
+    sniffed_kinds: HashMap<PathBuf, Option<Cow<'static, str>>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -234,12 +253,15 @@ impl App {
             scroll_offset: 0,
             last_selection_change: Instant::now(),
             preview_path: None,
+            usage_view: None,
+            sniffed_kinds: HashMap::new(),
         }
     }
 
     fn refresh_entries(&mut self) {
         let (entries, is_large, total) = list_dir_lazy(&self.current_dir, LAZY_LOAD_LIMIT);
         self.all_entries = entries;
+        self.sniffed_kinds.clear();
         self.is_large_dir = is_large;
         self.total_entries = total;
         self.apply_filters();
@@ -253,15 +275,12 @@ impl App {
         self.filtered_entries = self.all_entries
             .iter()
             .filter(|p| {
-                if !self.file_filter.matches(p) {
-                    return false;
-                }
-                if query.is_empty() {
-                    return true;
-                }
-                p.file_name()
-                    .map(|n| n.to_string_lossy().to_lowercase().contains(&query))
-                    .unwrap_or(false)
+                // Cheap name check first; the type filter may have to sniff
+                let name_matches = query.is_empty()
+                    || p.file_name()
+                        .map(|n| n.to_string_lossy().to_lowercase().contains(&query))
+                        .unwrap_or(false);
+                name_matches && self.file_filter.matches(p, &mut self.sniffed_kinds)
             })
             .cloned()
             .collect();
@@ -461,12 +480,7 @@ impl App {
     }
 
     fn next_free_bookmark_slot(&self) -> Option<usize> {
-        for i in 1..=MAX_BOOKMARKS {
-            if !self.bookmarks.contains_key(&i) {
-                return Some(i);
-            }
-        }
-        None
+        (1..=MAX_BOOKMARKS).find(|i| !self.bookmarks.contains_key(i))
     }
 
     fn enter_bookmark_jump_mode(&mut self) {
@@ -523,15 +537,17 @@ fn read_file_preview(path: &PathBuf) -> Option<String> {
     let bytes_read = file.read(&mut buffer).ok()?;
     buffer.truncate(bytes_read);
 
-    let content = String::from_utf8_lossy(&buffer).to_string();
-
-    if content.chars().take(512).filter(|c| c.is_control() && *c != '\n' && *c != '\r' && *c != '\t').count() > 10 {
+    // Same binary rule and decoding as the analysis engine
+    let Some(content) = encoding::decode_text(&buffer) else {
         let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let ext = path.extension().and_then(|e| e.to_str());
+        let detected = sniff::sniff(&buffer, ext);
+        let mismatch = if detected.extension_mismatch { "\n⚠️  Extension does not match content" } else { "" };
         return Some(format!(
-            "📦 Binary file\n\nSize: {} bytes\n\nPreview not available",
-            size
+            "📦 Binary file\n\nSize: {} bytes\nType: {} ({:.0}%){}\n\nPreview not available",
+            size, detected.mime_type, detected.confidence * 100.0, mismatch
         ));
-    }
+    };
 
     let truncated = if bytes_read == PREVIEW_MAX_BYTES {
         format!("{}\n\n... (truncated)", content)
@@ -542,6 +558,14 @@ fn read_file_preview(path: &PathBuf) -> Option<String> {
     Some(truncated)
 }
 
+/// Sniff a file's content type from its header
+fn sniff_path(path: &Path) -> Option<sniff::ContentType> {
+    let mut file = File::open(path).ok()?;
+    let mut head = Vec::with_capacity(sniff::SNIFF_LEN);
+    file.by_ref().take(sniff::SNIFF_LEN as u64).read_to_end(&mut head).ok()?;
+    Some(sniff::sniff(&head, None))
+}
+
 /// List directory with lazy loading
 fn list_dir_lazy(path: &PathBuf, limit: usize) -> (Vec<PathBuf>, bool, usize) {
     let read_dir = match fs::read_dir(path) {
@@ -720,9 +744,10 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Resul
                         let dest = app.dest.as_ref().unwrap();
 
                         match run_analysis(source, dest, &app.config).await {
-                            Ok(summary) => {
+                            Ok((summary, usage)) => {
                                 app.state = AppState::Results;
                                 app.message = Some(summary);
+                                app.usage_view = Some(usage_view(&usage));
                             }
                             Err(e) => {
                                 app.state = AppState::Error;
@@ -736,6 +761,7 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Resul
                         app.source = None;
                         app.dest = None;
                         app.state = AppState::SelectSource;
+                        app.usage_view = None;
                         app.message = Some("🔄 Selection reset".to_string());
                     }
 
@@ -746,15 +772,57 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Resul
     }
 }
 
-async fn run_analysis(source: &str, dest: &str, config: &Config) -> Result<String> {
-    let _lock = ReadOnlyLock::acquire(source).await?;
-    let results = analyzer::analyze(source, config).await?;
+async fn run_analysis(source: &str, dest: &str, config: &Config) -> Result<(String, UsageReport)> {
+    let lock = ReadOnlyLock::acquire(source).await?;
+    let mut config = config.clone();
+    config.analysis.ro_locked = true;
+    let mut results = analyzer::analyze(source, &config).await?;
+    if config.secrets.enabled {
+        tokio::task::block_in_place(|| secrets::scan_results(&lock, &mut results, &config.secrets))?;
+    }
     let summary = format!(
-        "✅ Analyzed {} files ({} bytes) → {}",
-        results.total_files, results.total_size, dest
+        "✅ Analyzed {} files ({} bytes, {} ignore rules, {} possible secrets) → {}",
+        results.total_files, results.total_size, results.ignore_rules.len(), results.secrets.len(), dest
     );
     analyzer::export(&results, dest).await?;
-    Ok(summary)
+    Ok((summary, results.usage))
+}
+
+/// Results view: largest directories and files, and the size histogram
+fn usage_view(usage: &UsageReport) -> String {
+    let mut lines = vec!["📁 Largest directories".to_string()];
+    lines.extend(usage.top_directories.iter().map(|d| format!("{:>10}  {}", format_bytes(d.size), d.path)));
+    lines.push(String::new());
+    lines.push("📄 Largest files".to_string());
+    lines.extend(usage.top_files.iter().map(|f| format!("{:>10}  {}", format_bytes(f.size), f.path)));
+    lines.push(String::new());
+    lines.push("📊 File sizes".to_string());
+    let most = usage.size_histogram.iter().map(|b| b.files).max().unwrap_or(0).max(1);
+    lines.extend(usage.size_histogram.iter().map(|b| {
+        let range = match b.min {
+            0 => "empty".to_string(),
+            min => format!("{}–{}", format_bytes(min), format_bytes(b.max)),
+        };
+        let bar = "█".repeat((b.files * HISTOGRAM_WIDTH).div_ceil(most));
+        format!("{:>19} {} {}", range, bar, b.files)
+    }));
+    lines.join("\n")
+}
+
+/// Size in binary units (`1.5 MiB`)
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
 }
 
 fn ui(frame: &mut Frame, app: &App) {
@@ -878,18 +946,21 @@ fn ui(frame: &mut Frame, app: &App) {
     }
     frame.render_stateful_widget(list, content_chunks[0], &mut state);
 
-    // Preview pane
-    let preview_text = match &app.preview_state {
-        PreviewState::Loading => "⏳ Loading preview...".to_string(),
-        PreviewState::Ready(content) => content.clone(),
-        PreviewState::None => "No preview available".to_string(),
+    // Preview pane, or the disk usage of the analyzed tree once results are in
+    let (preview_title, preview_text) = match (&app.usage_view, app.state) {
+        (Some(view), AppState::Results) => ("📊 Disk Usage", view.clone()),
+        _ => ("📋 Preview", match &app.preview_state {
+            PreviewState::Loading => "⏳ Loading preview...".to_string(),
+            PreviewState::Ready(content) => content.clone(),
+            PreviewState::None => "No preview available".to_string(),
+        }),
     };
 
     let preview = Paragraph::new(preview_text)
         .block(Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(theme.dim()))
-            .title("📋 Preview"))
+            .title(preview_title))
         .wrap(Wrap { trim: false })
         .style(Style::default().fg(theme.dim()));
     frame.render_widget(preview, content_chunks[1]);
@@ -1136,11 +1207,28 @@ mod tests {
     #[test]
     fn test_file_filter() {
         let filter = FileFilter::Extension("rs".to_string());
-        assert!(filter.matches(&PathBuf::from("/foo/bar.rs")));
-        assert!(!filter.matches(&PathBuf::from("/foo/bar.txt")));
+        let mut sniffed = HashMap::new();
+        assert!(filter.matches(&PathBuf::from("/foo/bar.rs"), &mut sniffed));
+        assert!(!filter.matches(&PathBuf::from("/foo/bar.txt"), &mut sniffed));
 
         let temp = tempfile::tempdir().unwrap();
-        assert!(filter.matches(&temp.path().to_path_buf()));
+        assert!(filter.matches(temp.path(), &mut sniffed));
+    }
+
+    #[test]
+    fn test_file_filter_sniffs_extensionless() {
+        let temp = tempfile::tempdir().unwrap();
+        let script = temp.path().join("deploy");
+        fs::write(&script, "#!/bin/bash\necho deploy\n").unwrap();
+
+        let mut sniffed = HashMap::new();
+        assert!(FileFilter::Extension("sh".to_string()).matches(&script, &mut sniffed));
+        assert!(!FileFilter::Extension("rs".to_string()).matches(&script, &mut sniffed));
+        assert_eq!(sniffed.len(), 1);
+
+        // Cached: the kind survives the file going away
+        fs::remove_file(&script).unwrap();
+        assert!(FileFilter::Extension("sh".to_string()).matches(&script, &mut sniffed));
     }
 
     #[test]
@@ -1220,4 +1308,24 @@ mod tests {
         app.schedule_preview_update();
         assert!(matches!(app.preview_state, PreviewState::Loading));
     }
+
+    #[test]
+    fn test_usage_view() {
+        use crate::disk_usage::{SizeBucket, SizeEntry};
+
+        let usage = UsageReport {
+            top_directories: vec![SizeEntry { path: "/data/src".to_string(), size: 3 * 1024 * 1024 / 2 }],
+            top_files: vec![SizeEntry { path: "/data/src/a.bin".to_string(), size: 900 }],
+            size_histogram: vec![
+                SizeBucket { min: 0, max: 1, files: 2, bytes: 0 },
+                SizeBucket { min: 1, max: 1024, files: 4, bytes: 900 },
+            ],
+            ..Default::default()
+        };
+        let view = usage_view(&usage);
+        assert!(view.contains("   1.5 MiB  /data/src"));
+        assert!(view.contains("     900 B  /data/src/a.bin"));
+        assert!(view.contains(&format!("empty {} 2", "█".repeat(HISTOGRAM_WIDTH / 2))));
+        assert!(view.contains(&format!("1 B–1.0 KiB {} 4", "█".repeat(HISTOGRAM_WIDTH))));
+    }
 }
@@ -0,0 +1,258 @@
+//! Extended Attributes Module
+//!
+//! Metadata that mode bits do not show: SELinux labels, file capabilities
+//! and POSIX ACLs, plus every other extended attribute of a file. File
+//! capabilities are decoded into `getcap` form and ACLs into `getfacl`
+//! entries. Collection is opt-in (`[analysis] extended_attributes`) since it
+//! costs extra system calls per file.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ownership;
+
+pub const SELINUX: &str = "security.selinux";
+pub const CAPABILITY: &str = "security.capability";
+pub const ACL_ACCESS: &str = "system.posix_acl_access";
+pub const ACL_DEFAULT: &str = "system.posix_acl_default";
+
+/// Longest attribute value kept, in bytes
+const MAX_VALUE_LEN: usize = 4096;
+
+const VFS_CAP_REVISION_MASK: u32 = 0xFF00_0000;
+const VFS_CAP_REVISION_1: u32 = 0x0100_0000;
+const VFS_CAP_REVISION_3: u32 = 0x0300_0000;
+const VFS_CAP_FLAGS_EFFECTIVE: u32 = 0x1;
+
+const ACL_VERSION: u32 = 2;
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+
+/// Linux capability names by bit number
+const CAPABILITIES: &[&str] = &[
+    "cap_chown", "cap_dac_override", "cap_dac_read_search", "cap_fowner", "cap_fsetid",
+    "cap_kill", "cap_setgid", "cap_setuid", "cap_setpcap", "cap_linux_immutable",
+    "cap_net_bind_service", "cap_net_broadcast", "cap_net_admin", "cap_net_raw",
+    "cap_ipc_lock", "cap_ipc_owner", "cap_sys_module", "cap_sys_rawio", "cap_sys_chroot",
+    "cap_sys_ptrace", "cap_sys_pacct", "cap_sys_admin", "cap_sys_boot", "cap_sys_nice",
+    "cap_sys_resource", "cap_sys_time", "cap_sys_tty_config", "cap_mknod", "cap_lease",
+    "cap_audit_write", "cap_audit_control", "cap_setfcap", "cap_mac_override", "cap_mac_admin",
+    "cap_syslog", "cap_wake_alarm", "cap_block_suspend", "cap_audit_read", "cap_perfmon",
+    "cap_bpf", "cap_checkpoint_restore",
+];
+
+/// Extended attributes of a file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedAttributes {
+    /// SELinux context (`system_u:object_r:bin_t:s0`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selinux: Option<String>,
+    /// File capabilities in `getcap` form (`cap_net_raw=ep`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<String>,
+    /// Access ACL in `getfacl` form (`user:alice:rw-`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub acl: Vec<String>,
+    /// Default ACL inherited by new entries of a directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_acl: Vec<String>,
+    /// Every attribute by name: text when printable, else `hex:` bytes
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Read and decode the attributes of `path` (following symlinks);
+/// `None` when it has none or the platform has no extended attributes
+#[cfg(unix)]
+pub fn read(path: &std::path::Path) -> std::io::Result<Option<ExtendedAttributes>> {
+    let mut attrs = ExtendedAttributes::default();
+    for name in xattr::list_deref(path)? {
+        let name = name.to_string_lossy().into_owned();
+        let Some(value) = xattr::get_deref(path, &name)? else {
+            continue;
+        };
+        attrs.add(&name, &value);
+    }
+    Ok((!attrs.attributes.is_empty()).then_some(attrs))
+}
+
+#[cfg(not(unix))]
+pub fn read(_path: &std::path::Path) -> std::io::Result<Option<ExtendedAttributes>> {
+    Ok(None)
+}
+
+impl ExtendedAttributes {
+    /// Record one raw attribute, decoding the well-known ones
+    pub fn add(&mut self, name: &str, value: &[u8]) {
+        match name {
+            SELINUX => self.selinux = Some(text(value)),
+            CAPABILITY => self.capabilities = decode_capabilities(value),
+            ACL_ACCESS => self.acl = decode_acl(value).unwrap_or_default(),
+            ACL_DEFAULT => self.default_acl = decode_acl(value).unwrap_or_default(),
+            _ => {}
+        }
+        self.attributes.insert(name.to_string(), display(value));
+    }
+}
+
+/// Printable text without the trailing NUL, else `hex:` and the bytes
+fn display(value: &[u8]) -> String {
+    let value = &value[..value.len().min(MAX_VALUE_LEN)];
+    let trimmed = value.strip_suffix(b"\0").unwrap_or(value);
+    match std::str::from_utf8(trimmed) {
+        Ok(s) if !s.chars().any(char::is_control) => s.to_string(),
+        _ => {
+            let hex: String = value.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("hex:{}", hex)
+        }
+    }
+}
+
+fn text(value: &[u8]) -> String {
+    let value = value.strip_suffix(b"\0").unwrap_or(value);
+    String::from_utf8_lossy(value).into_owned()
+}
+
+fn le32(data: &[u8], offset: usize) -> Option<u32> {
+    let b = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// `vfs_cap_data` as `getcap` prints it: capabilities grouped by their
+/// effective/inheritable/permitted flags
+pub fn decode_capabilities(data: &[u8]) -> Option<String> {
+    let magic = le32(data, 0)?;
+    let revision = magic & VFS_CAP_REVISION_MASK;
+    let effective = magic & VFS_CAP_FLAGS_EFFECTIVE != 0;
+    let words = if revision == VFS_CAP_REVISION_1 { 1 } else { 2 };
+
+    let (mut permitted, mut inheritable) = (0u64, 0u64);
+    for word in 0..words {
+        permitted |= (le32(data, 4 + word * 8)? as u64) << (32 * word);
+        inheritable |= (le32(data, 8 + word * 8)? as u64) << (32 * word);
+    }
+
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for bit in 0..64 {
+        let (p, i) = (permitted >> bit & 1 == 1, inheritable >> bit & 1 == 1);
+        if !p && !i {
+            continue;
+        }
+        let flags = format!(
+            "{}{}{}",
+            if effective && p { "e" } else { "" },
+            if i { "i" } else { "" },
+            if p { "p" } else { "" },
+        );
+        let name = CAPABILITIES.get(bit).map_or_else(|| format!("cap_{}", bit), |n| n.to_string());
+        match groups.iter_mut().find(|(f, _)| *f == flags) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((flags, vec![name])),
+        }
+    }
+
+    let mut out: Vec<String> = groups.into_iter()
+        .map(|(flags, names)| format!("{}={}", names.join(","), flags))
+        .collect();
+    if revision == VFS_CAP_REVISION_3 {
+        out.push(format!("[rootid={}]", le32(data, 20)?));
+    }
+    (!out.is_empty()).then(|| out.join(" "))
+}
+
+/// POSIX ACL xattr as `getfacl` entries, with user and group names resolved
+pub fn decode_acl(data: &[u8]) -> Option<Vec<String>> {
+    if le32(data, 0)? != ACL_VERSION {
+        return None;
+    }
+    data[4..]
+        .chunks_exact(8)
+        .map(|entry| {
+            let tag = u16::from_le_bytes([entry[0], entry[1]]);
+            let perm = u16::from_le_bytes([entry[2], entry[3]]);
+            let id = le32(entry, 4)?;
+            let (kind, qualifier) = match tag {
+                ACL_USER_OBJ => ("user", String::new()),
+                ACL_USER => ("user", ownership::user_name(id).unwrap_or_else(|| id.to_string())),
+                ACL_GROUP_OBJ => ("group", String::new()),
+                ACL_GROUP => ("group", ownership::group_name(id).unwrap_or_else(|| id.to_string())),
+                ACL_MASK => ("mask", String::new()),
+                ACL_OTHER => ("other", String::new()),
+                _ => return None,
+            };
+            let rwx: String = [(4, 'r'), (2, 'w'), (1, 'x')]
+                .iter()
+                .map(|&(bit, c)| if perm & bit != 0 { c } else { '-' })
+                .collect();
+            Some(format!("{}:{}:{}", kind, qualifier, rwx))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_decode_capabilities() {
+        // ping: cap_net_raw (13) permitted and effective, revision 2
+        let ping = words(&[0x0200_0001, 1 << 13, 0, 0, 0]);
+        assert_eq!(decode_capabilities(&ping).as_deref(), Some("cap_net_raw=ep"));
+
+        // Mixed flags, a high capability and a namespaced root id (revision 3)
+        let mixed = words(&[0x0300_0000, 1 << 10 | 1 << 12, 1 << 12, 1 << (39 - 32), 0, 1000]);
+        assert_eq!(
+            decode_capabilities(&mixed).as_deref(),
+            Some("cap_net_bind_service,cap_bpf=p cap_net_admin=ip [rootid=1000]")
+        );
+        assert_eq!(decode_capabilities(b"\x02"), None);
+    }
+
+    #[test]
+    fn test_decode_acl() {
+        let mut acl = words(&[ACL_VERSION]);
+        for (tag, perm, id) in [
+            (ACL_USER_OBJ, 6, u32::MAX),
+            (ACL_USER, 4, 0),
+            (ACL_USER, 7, 4_000_000_001),
+            (ACL_GROUP_OBJ, 4, u32::MAX),
+            (ACL_MASK, 7, u32::MAX),
+            (ACL_OTHER, 0, u32::MAX),
+        ] {
+            acl.extend(tag.to_le_bytes());
+            acl.extend((perm as u16).to_le_bytes());
+            acl.extend(id.to_le_bytes());
+        }
+        assert_eq!(decode_acl(&acl).unwrap(), [
+            "user::rw-", "user:root:r--", "user:4000000001:rwx", "group::r--", "mask::rwx", "other::---",
+        ]);
+        assert_eq!(decode_acl(&words(&[1])), None);
+    }
+
+    #[test]
+    fn test_read_attributes() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("tagged");
+        std::fs::write(&path, "x").unwrap();
+        assert_eq!(read(&path).unwrap(), None);
+        // Not every filesystem takes user attributes
+        if xattr::set(&path, "user.origin", b"https://example.com\0").is_err() {
+            return;
+        }
+        xattr::set(&path, "user.blob", &[0xff, 0x00, 0x01]).unwrap();
+
+        let attrs = read(&path).unwrap().unwrap();
+        assert_eq!(attrs.attributes["user.origin"], "https://example.com");
+        assert_eq!(attrs.attributes["user.blob"], "hex:ff0001");
+        assert_eq!(attrs.selinux, None);
+    }
+}